@@ -1,9 +1,11 @@
 use crate::error::{AppError, AppResult};
-use crate::storage::{ApiConfig, SecureStorage};
+use crate::resolve;
+use crate::storage::ApiConfig;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
-use reqwest::{header, Client};
+use reqwest::{header, Client, RequestBuilder, Response, StatusCode};
 use serde_json::Value;
 
+use super::transport::{self, Diagnostics};
 use super::types::*;
 
 /// HTTP client for Onetimesecret API
@@ -11,12 +13,14 @@ pub struct OtsClient {
     client: Client,
     base_url: String,
     auth_header: String,
+    diagnostics: Diagnostics,
 }
 
 impl OtsClient {
-    /// Create a new API client from stored configuration
+    /// Create a new API client from the resolved configuration
+    /// Environment variables and a config file take precedence over the keychain
     pub async fn from_config() -> AppResult<Self> {
-        let config = SecureStorage::load_config()?
+        let config = resolve::api_config()?
             .ok_or_else(|| AppError::Configuration("No API configuration found".to_string()))?;
 
         Self::new(config).await
@@ -26,38 +30,54 @@ impl OtsClient {
     pub async fn new(config: ApiConfig) -> AppResult<Self> {
         let api_key = config
             .api_key
+            .as_deref()
             .ok_or_else(|| AppError::Authentication("No API key configured".to_string()))?;
 
         // Create Basic Auth header: base64(username:api_key)
         let auth_string = format!("{}:{}", config.username, api_key);
         let auth_header = format!("Basic {}", BASE64.encode(auth_string.as_bytes()));
 
-        // Build HTTP client with security settings
-        let client = Client::builder()
-            .user_agent("OnetimesecretDesktop/0.1.0")
-            .timeout(std::time::Duration::from_secs(30))
-            .https_only(true)
-            .build()?;
+        // Build HTTP client with security, proxy and certificate settings
+        let (client, diagnostics) = transport::build_client(&config)?;
 
         Ok(Self {
             client,
             base_url: config.base_url.trim_end_matches('/').to_string(),
             auth_header,
+            diagnostics,
         })
     }
 
+    /// Send a request, reporting whether the proxy, certificate or API failed
+    async fn send(&self, request: RequestBuilder) -> AppResult<Response> {
+        request
+            .send()
+            .await
+            .map_err(|e| self.diagnostics.request_error(e))
+    }
+
     /// Test API connection and authentication
+    /// Rejected credentials, at the API or the proxy, are reported as errors
     pub async fn test_connection(&self) -> AppResult<bool> {
         let url = format!("{}/api/v2/status", self.base_url);
 
         let response = self
-            .client
-            .get(&url)
-            .header(header::AUTHORIZATION, &self.auth_header)
-            .send()
+            .send(
+                self.client
+                    .get(&url)
+                    .header(header::AUTHORIZATION, &self.auth_header),
+            )
             .await?;
 
-        Ok(response.status().is_success())
+        match response.status() {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(AppError::Authentication(
+                format!("API rejected the credentials ({})", response.status()),
+            )),
+            StatusCode::PROXY_AUTHENTICATION_REQUIRED => Err(AppError::Proxy(
+                "Proxy rejected the credentials (407)".to_string(),
+            )),
+            status => Ok(status.is_success()),
+        }
     }
 
     /// Create a new secret
@@ -76,19 +96,24 @@ impl OtsClient {
         let url = format!("{}/api/v2/share", self.base_url);
 
         let response = self
-            .client
-            .post(&url)
-            .header(header::AUTHORIZATION, &self.auth_header)
-            .header(header::CONTENT_TYPE, "application/json")
-            .json(&serde_json::json!({
-                "secret": request.secret,
-                "passphrase": request.passphrase,
-                "ttl": request.ttl,
-                "recipient": request.recipient,
-            }))
-            .send()
+            .send(
+                self.client
+                    .post(&url)
+                    .header(header::AUTHORIZATION, &self.auth_header)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .json(&serde_json::json!({
+                        "secret": request.secret,
+                        "passphrase": request.passphrase,
+                        "ttl": request.ttl,
+                        "recipient": request.recipient,
+                    })),
+            )
             .await?;
 
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            return Err(AppError::RateLimited(retry_after(&response)));
+        }
+
         if !response.status().is_success() {
             let status = response.status();
             let error_text = response.text().await.unwrap_or_default();
@@ -148,7 +173,7 @@ impl OtsClient {
             req = req.json(&serde_json::json!({}));
         }
 
-        let response = req.send().await?;
+        let response = self.send(req).await?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -183,12 +208,17 @@ impl OtsClient {
         let url = format!("{}/api/v2/private/{}", self.base_url, metadata_key);
 
         let response = self
-            .client
-            .post(&url)
-            .header(header::AUTHORIZATION, &self.auth_header)
-            .send()
+            .send(
+                self.client
+                    .post(&url)
+                    .header(header::AUTHORIZATION, &self.auth_header),
+            )
             .await?;
 
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            return Err(AppError::RateLimited(retry_after(&response)));
+        }
+
         if !response.status().is_success() {
             let status = response.status();
             let error_text = response.text().await.unwrap_or_default();
@@ -201,6 +231,55 @@ impl OtsClient {
         let metadata: SecretMetadata = response.json().await?;
         Ok(metadata)
     }
+
+    /// Burn a secret before it is viewed, returning its updated metadata
+    pub async fn burn_secret(&self, metadata_key: &str) -> AppResult<SecretMetadata> {
+        if metadata_key.is_empty() {
+            return Err(AppError::InvalidInput(
+                "Metadata key cannot be empty".to_string(),
+            ));
+        }
+
+        let url = format!("{}/api/v2/private/{}/burn", self.base_url, metadata_key);
+
+        let response = self
+            .send(
+                self.client
+                    .post(&url)
+                    .header(header::AUTHORIZATION, &self.auth_header)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .json(&serde_json::json!({ "continue": true })),
+            )
+            .await?;
+
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            return Err(AppError::RateLimited(retry_after(&response)));
+        }
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(AppError::Api(format!(
+                "Failed to burn secret: {} - {}",
+                status, error_text
+            )));
+        }
+
+        let metadata: SecretMetadata = response.json().await?;
+        Ok(metadata)
+    }
+}
+
+/// Seconds to wait from a `Retry-After` header, when given as a number
+fn retry_after(response: &Response) -> Option<u64> {
+    response
+        .headers()
+        .get(header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
 }
 
 #[cfg(test)]
@@ -1,4 +1,6 @@
 pub mod client;
+pub mod tls;
+pub mod transport;
 pub mod types;
 
 pub use client::OtsClient;
@@ -0,0 +1,229 @@
+use super::tls;
+use crate::error::{AppError, AppResult};
+use crate::storage::{ApiConfig, ProxyMode, ProxySettings, SecureStorage};
+use reqwest::redirect::Policy;
+use reqwest::{Client, NoProxy, Proxy};
+use std::time::Duration;
+
+/// Redirects followed before a request fails, as with reqwest's default policy
+const MAX_REDIRECTS: usize = 10;
+
+/// What a client needs to explain its failed requests
+#[derive(Debug, Clone)]
+pub struct Diagnostics {
+    proxy: ProxySettings,
+    client_certificate: bool,
+}
+
+/// Build the HTTP client for a profile's connection settings
+pub fn build_client(config: &ApiConfig) -> AppResult<(Client, Diagnostics)> {
+    let tls_config = tls::client_config(&config.tls)?;
+
+    let builder = Client::builder()
+        .user_agent("OnetimesecretDesktop/0.1.0")
+        // Stalled connections time out, while long uploads run until done
+        // or cancelled, see operations
+        .connect_timeout(Duration::from_secs(30))
+        .read_timeout(Duration::from_secs(30))
+        .use_preconfigured_tls(tls_config);
+
+    // Plain HTTP is only for a validated local base URL, and then redirects
+    // may not leave it; everything else, redirects included, needs HTTPS
+    let builder = match plain_http_origin(config) {
+        Some(origin) => builder.https_only(false).redirect(same_origin(origin)),
+        None => builder.https_only(true),
+    };
+
+    // System mode keeps reqwest's default of reading the proxy environment
+    // variables and, on macOS and Windows, the OS proxy settings
+    let builder = match config.proxy.mode {
+        ProxyMode::System => builder,
+        ProxyMode::Direct => builder.no_proxy(),
+        ProxyMode::Manual => builder.proxy(proxy(&config.proxy)?),
+    };
+
+    let diagnostics = Diagnostics {
+        proxy: config.proxy.clone(),
+        client_certificate: config.tls.client_certificate.is_some(),
+    };
+
+    Ok((builder.build()?, diagnostics))
+}
+
+/// Origin of the base URL if it is plain HTTP that `validate_base_url`
+/// allows, i.e. a local server with the developer option on
+fn plain_http_origin(config: &ApiConfig) -> Option<url::Origin> {
+    let url = url::Url::parse(&config.base_url).ok()?;
+    (url.scheme() == "http" && SecureStorage::validate_base_url(config).is_ok())
+        .then(|| url.origin())
+}
+
+/// Follow redirects only within `origin`
+fn same_origin(origin: url::Origin) -> Policy {
+    Policy::custom(move |attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            attempt.error("too many redirects")
+        } else if attempt.url().origin() != origin {
+            attempt.error("refusing to follow a redirect away from the local server")
+        } else {
+            attempt.follow()
+        }
+    })
+}
+
+fn proxy(settings: &ProxySettings) -> AppResult<Proxy> {
+    let url = settings
+        .url
+        .as_deref()
+        .ok_or_else(|| AppError::Proxy("No proxy URL configured".to_string()))?;
+
+    let mut proxy =
+        Proxy::all(url).map_err(|e| AppError::Proxy(format!("Invalid proxy URL: {}", e)))?;
+
+    // Also applies to SOCKS5 username/password authentication
+    if let Some(username) = &settings.username {
+        proxy = proxy.basic_auth(username, settings.password.as_deref().unwrap_or_default());
+    }
+
+    if !settings.no_proxy.is_empty() {
+        proxy = proxy.no_proxy(NoProxy::from_string(&settings.no_proxy.join(",")));
+    }
+
+    Ok(proxy)
+}
+
+impl Diagnostics {
+    /// Map a failed request to the part of the connection that failed
+    ///
+    /// Rejected server certificates become `Certificate` errors showing the
+    /// presented chain, failures to reach or tunnel through the proxy become
+    /// `Proxy` errors, TLS alerts become `Handshake` errors, and anything else
+    /// is a network error reaching the API.
+    pub fn request_error(&self, err: reqwest::Error) -> AppError {
+        if let Some(err) = tls::certificate_error(&err) {
+            return err;
+        }
+
+        let detail = error_chain(&err);
+
+        if self.proxy.mode != ProxyMode::Direct && is_proxy_failure(&detail) {
+            return AppError::Proxy(detail);
+        }
+
+        if is_handshake_failure(&detail) {
+            return AppError::Handshake(self.handshake_hint(detail));
+        }
+
+        AppError::Network(detail)
+    }
+
+    /// mTLS gateways reject a missing or unknown client certificate with an alert
+    fn handshake_hint(&self, detail: String) -> String {
+        if self.client_certificate {
+            format!(
+                "{} (check that the server accepts the client certificate)",
+                detail
+            )
+        } else if detail.contains("CertificateRequired") {
+            format!(
+                "{} (the server requires a client certificate, add one to the profile)",
+                detail
+            )
+        } else {
+            detail
+        }
+    }
+}
+
+/// reqwest only shows the outermost error, the cause is further down
+fn error_chain(err: &dyn std::error::Error) -> String {
+    let mut detail = err.to_string();
+    let mut source = err.source();
+
+    while let Some(cause) = source {
+        detail.push_str(": ");
+        detail.push_str(&cause.to_string());
+        source = cause.source();
+    }
+
+    detail
+}
+
+fn is_proxy_failure(detail: &str) -> bool {
+    // Messages of the HTTP CONNECT tunnel and SOCKS connectors
+    detail.contains("tunnel error") || detail.contains("SOCKS error")
+}
+
+fn is_handshake_failure(detail: &str) -> bool {
+    // rustls alert and protocol errors, surfaced through the connector
+    detail.contains("received fatal alert")
+        || detail.contains("peer is incompatible")
+        || detail.contains("peer misbehaved")
+        || detail.contains("handshake")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_client_with_proxy() {
+        let mut config = ApiConfig {
+            base_url: "https://onetimesecret.com".to_string(),
+            username: "test@example.com".to_string(),
+            ..Default::default()
+        };
+        assert!(build_client(&config).is_ok());
+
+        config.proxy = ProxySettings {
+            mode: ProxyMode::Manual,
+            url: Some("socks5h://proxy.corp:1080".to_string()),
+            username: Some("user".to_string()),
+            password: Some("secret".to_string()),
+            no_proxy: vec!["localhost".to_string(), ".corp.example.com".to_string()],
+        };
+        assert!(build_client(&config).is_ok());
+
+        config.proxy.url = None;
+        assert!(matches!(build_client(&config), Err(AppError::Proxy(_))));
+    }
+
+    #[test]
+    fn test_plain_http_only_for_local_base_url() {
+        let mut config = ApiConfig {
+            base_url: "http://localhost:3000".to_string(),
+            username: "test@example.com".to_string(),
+            ..Default::default()
+        };
+        assert!(plain_http_origin(&config).is_none());
+
+        config.allow_insecure_http = true;
+        let origin = plain_http_origin(&config).unwrap();
+        assert_eq!(origin.ascii_serialization(), "http://localhost:3000");
+
+        config.base_url = "http://onetimesecret.com".to_string();
+        assert!(plain_http_origin(&config).is_none());
+        config.base_url = "https://onetimesecret.com".to_string();
+        assert!(plain_http_origin(&config).is_none());
+    }
+
+    #[test]
+    fn test_is_proxy_failure() {
+        assert!(is_proxy_failure(
+            "error sending request: client error (Connect): tunnel error: proxy authorization required"
+        ));
+        assert!(is_proxy_failure("error sending request: SOCKS error: io error during SOCKS handshake"));
+        assert!(!is_proxy_failure("error sending request: dns error: failed to lookup address"));
+    }
+
+    #[test]
+    fn test_is_handshake_failure() {
+        assert!(is_handshake_failure(
+            "error sending request: client error (Connect): received fatal alert: CertificateRequired"
+        ));
+        assert!(is_handshake_failure(
+            "error sending request: client error (Connect): peer is incompatible: Tls12NotOffered"
+        ));
+        assert!(!is_handshake_failure("error sending request: operation timed out"));
+    }
+}
@@ -51,6 +51,9 @@ pub struct SecretMetadata {
     pub updated_at: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub recipient: Option<Vec<String>>,
+    /// e.g. `new`, `received`, `burned` or `expired`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
 }
 
 /// API status response
@@ -0,0 +1,487 @@
+use crate::error::{AppError, AppResult};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Marks a secret as a file envelope rather than plain text
+pub const ENVELOPE_TYPE: &str = "onetimesecret/file";
+
+/// Envelope format written by this build
+const ENVELOPE_VERSION: u32 = 1;
+
+/// Longest accepted file name, longer names are cut when saving
+const MAX_NAME_LENGTH: usize = 255;
+
+/// Bytes of a text file shown before sharing
+const PREVIEW_LENGTH: usize = 512;
+
+/// Name of the archive when several files are shared as one secret
+const ARCHIVE_NAME: &str = "shared-files.zip";
+
+/// A file wrapped with its name and MIME type so it can travel as a secret
+///
+/// Serialized as JSON with the contents in base64. The `type` field lets
+/// the receiving side tell it apart from a secret that merely looks like JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileEnvelope {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub version: u32,
+    pub name: String,
+    pub mime_type: String,
+    pub size: u64,
+    /// Hex SHA-256 of the contents, checked before saving
+    pub sha256: String,
+    pub data: String,
+}
+
+/// What the frontend is told about a received file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReceivedFile {
+    pub id: String,
+    pub name: String,
+    pub mime_type: String,
+    pub size: u64,
+}
+
+/// Request to share a file as a secret
+#[derive(Debug, Deserialize)]
+pub struct ShareFileRequest {
+    pub path: String,
+    #[serde(default)]
+    pub passphrase: Option<String>,
+    pub ttl: u32,
+    #[serde(default)]
+    pub recipient: Option<String>,
+}
+
+/// Request to share several files, e.g. dropped on the window
+#[derive(Debug, Deserialize)]
+pub struct ShareFilesRequest {
+    /// Operation ID, to cancel the upload and match its progress events
+    #[serde(default)]
+    pub id: Option<String>,
+    pub paths: Vec<String>,
+    #[serde(default)]
+    pub mode: ShareMode,
+    /// Split files larger than the secret size limit across several secrets
+    #[serde(default)]
+    pub chunked: bool,
+    /// Name of a known recipient to encrypt the files to
+    #[serde(default)]
+    pub encrypt_to: Option<String>,
+    #[serde(default)]
+    pub passphrase: Option<String>,
+    pub ttl: u32,
+    #[serde(default)]
+    pub recipient: Option<String>,
+}
+
+/// How several files become secrets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShareMode {
+    /// One secret holding a zip archive of all files
+    #[default]
+    Archive,
+    /// One secret per file
+    Separate,
+}
+
+/// What the confirmation step shows about a file before it is shared
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FilePreview {
+    pub path: String,
+    pub name: String,
+    pub mime_type: String,
+    pub size: u64,
+    /// Start of the contents for text files, None for binary files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview: Option<String>,
+    /// Whether the file alone exceeds the secret size limit
+    pub too_large: bool,
+}
+
+impl FileEnvelope {
+    /// Wrap file contents under the given name
+    pub fn new(name: &str, contents: &[u8]) -> Self {
+        Self {
+            kind: ENVELOPE_TYPE.to_string(),
+            version: ENVELOPE_VERSION,
+            name: name.to_string(),
+            mime_type: mime_type(name).to_string(),
+            size: contents.len() as u64,
+            sha256: hex_digest(contents),
+            data: BASE64.encode(contents),
+        }
+    }
+
+    /// Read a file and check that its envelope fits in `max_size` bytes
+    pub fn read(path: &Path, max_size: usize) -> AppResult<Self> {
+        let name = file_name(path)?;
+
+        // Base64 grows the contents by a third, fail before reading big files
+        let size = std::fs::metadata(path)?.len();
+        if size > max_file_size(max_size) as u64 {
+            return Err(too_large(size, max_size));
+        }
+
+        Self::sized(&name, &std::fs::read(path)?, size, max_size)
+    }
+
+    /// Wrap contents, failing if the envelope exceeds `max_size` bytes
+    ///
+    /// `size` is what the error reports, the size of the shared files
+    /// rather than of the archive holding them.
+    fn sized(name: &str, contents: &[u8], size: u64, max_size: usize) -> AppResult<Self> {
+        let envelope = Self::new(name, contents);
+        if envelope.encode()?.len() > max_size {
+            return Err(too_large(size, max_size));
+        }
+
+        Ok(envelope)
+    }
+
+    /// Read several files into one zip archive that fits in `max_size` bytes
+    ///
+    /// Files with the same name from different folders are kept apart by
+    /// numbering the later ones.
+    pub fn archive(paths: &[PathBuf], max_size: usize) -> AppResult<Self> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        let mut names = Vec::new();
+
+        // Fail before reading anything when the files alone are too big
+        let mut total = 0;
+        for path in paths {
+            total += std::fs::metadata(path)?.len();
+        }
+        if total > max_file_size(max_size) as u64 {
+            return Err(too_large(total, max_size));
+        }
+
+        for path in paths {
+            let name = unique_name(&file_name(path)?, &names);
+            let contents = std::fs::read(path)?;
+
+            zip.start_file(name.as_str(), options)
+                .map_err(archive_error)?;
+            zip.write_all(&contents)?;
+            names.push(name);
+        }
+
+        let archive = zip.finish().map_err(archive_error)?.into_inner();
+        Self::sized(ARCHIVE_NAME, &archive, total, max_size)
+    }
+
+    pub fn encode(&self) -> AppResult<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// The envelope in a retrieved secret, or None for ordinary text
+    pub fn detect(secret: &str) -> Option<Self> {
+        if !secret.trim_start().starts_with('{') {
+            return None;
+        }
+
+        serde_json::from_str::<Self>(secret)
+            .ok()
+            .filter(|envelope| envelope.kind == ENVELOPE_TYPE)
+    }
+
+    /// Decoded contents, checked against the size and hash
+    pub fn contents(&self) -> AppResult<Vec<u8>> {
+        if self.version > ENVELOPE_VERSION {
+            return Err(AppError::InvalidInput(format!(
+                "File was shared by a newer version (v{}), please update the app",
+                self.version
+            )));
+        }
+
+        let contents = BASE64
+            .decode(&self.data)
+            .map_err(|e| AppError::InvalidInput(format!("File contents are damaged: {}", e)))?;
+
+        if contents.len() as u64 != self.size || hex_digest(&contents) != self.sha256 {
+            return Err(AppError::InvalidInput(
+                "File contents do not match their checksum".to_string(),
+            ));
+        }
+
+        Ok(contents)
+    }
+
+    /// The name reduced to a plain file name, safe to offer in a save dialog
+    pub fn safe_name(&self) -> String {
+        let name: String = self
+            .name
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .filter(|c| !c.is_control())
+            .take(MAX_NAME_LENGTH)
+            .collect();
+
+        match name.trim_start_matches('.') {
+            "" => "secret-file".to_string(),
+            _ => name,
+        }
+    }
+}
+
+impl FilePreview {
+    /// Describe a file for the confirmation step without reading all of it
+    pub fn read(path: &Path, max_size: usize) -> AppResult<Self> {
+        let name = file_name(path)?;
+        let metadata = std::fs::metadata(path)?;
+        if !metadata.is_file() {
+            return Err(AppError::InvalidInput(format!(
+                "Not a file: {}",
+                path.display()
+            )));
+        }
+
+        let mut head = Vec::with_capacity(PREVIEW_LENGTH);
+        std::fs::File::open(path)?
+            .take(PREVIEW_LENGTH as u64)
+            .read_to_end(&mut head)?;
+
+        Ok(Self {
+            path: path.to_string_lossy().into_owned(),
+            mime_type: mime_type(&name).to_string(),
+            name,
+            size: metadata.len(),
+            preview: text_preview(&head),
+            too_large: metadata.len() > max_file_size(max_size) as u64,
+        })
+    }
+}
+
+/// Files from retrieved secrets, kept until saved or discarded
+///
+/// The secret is burned on retrieval, so the contents stay in memory
+/// instead of travelling to the frontend as base64.
+#[derive(Default)]
+pub struct ReceivedFiles {
+    files: Mutex<HashMap<String, FileEnvelope>>,
+}
+
+impl ReceivedFiles {
+    pub fn insert(&self, envelope: FileEnvelope) -> ReceivedFile {
+        let id = format!("{:016x}", rand::random::<u64>());
+        let received = ReceivedFile {
+            id: id.clone(),
+            name: envelope.safe_name(),
+            mime_type: envelope.mime_type.clone(),
+            size: envelope.size,
+        };
+
+        self.files.lock().unwrap().insert(id, envelope);
+        received
+    }
+
+    /// Put a file back after a failed save
+    pub fn restore(&self, id: &str, envelope: FileEnvelope) {
+        self.files.lock().unwrap().insert(id.to_string(), envelope);
+    }
+
+    pub fn take(&self, id: &str) -> AppResult<FileEnvelope> {
+        self.files.lock().unwrap().remove(id).ok_or_else(|| {
+            AppError::InvalidInput("Received file is no longer available".to_string())
+        })
+    }
+}
+
+fn file_name(path: &Path) -> AppResult<String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| AppError::InvalidInput(format!("Not a file: {}", path.display())))
+}
+
+/// `name`, or `name (2).ext` and so on if it is already taken
+fn unique_name(name: &str, taken: &[String]) -> String {
+    if !taken.iter().any(|t| t == name) {
+        return name.to_string();
+    }
+
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{}", extension)),
+        _ => (name, String::new()),
+    };
+    (2..)
+        .map(|n| format!("{} ({}){}", stem, n, extension))
+        .find(|candidate| !taken.contains(candidate))
+        .expect("unbounded range yields a free name")
+}
+
+/// The start of a file as text, or None if it does not look like text
+fn text_preview(head: &[u8]) -> Option<String> {
+    // The cut may split a multi-byte character at the end
+    let text = match std::str::from_utf8(head) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&head[..e.valid_up_to()]).ok()?,
+        Err(_) => return None,
+    };
+
+    text.chars()
+        .all(|c| !c.is_control() || c.is_whitespace())
+        .then(|| text.to_string())
+}
+
+fn archive_error(e: zip::result::ZipError) -> AppError {
+    AppError::Storage(format!("Failed to create archive: {}", e))
+}
+
+/// Largest file whose base64 envelope can fit in `max_size` bytes
+fn max_file_size(max_size: usize) -> usize {
+    max_size / 4 * 3
+}
+
+fn too_large(size: u64, max_size: usize) -> AppError {
+    AppError::InvalidInput(format!(
+        "File is {} bytes, the secret size limit in the settings allows files up to about {} bytes",
+        size,
+        max_file_size(max_size)
+    ))
+}
+
+fn hex_digest(contents: &[u8]) -> String {
+    Sha256::digest(contents)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// MIME type for the kinds of files usually shared as secrets
+fn mime_type(name: &str) -> &'static str {
+    let extension = name
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "txt" | "env" | "conf" | "cfg" | "ini" => "text/plain",
+        "json" => "application/json",
+        "yaml" | "yml" => "application/yaml",
+        "toml" => "application/toml",
+        "pem" | "crt" | "cer" | "key" | "pub" => "application/x-pem-file",
+        "p12" | "pfx" => "application/x-pkcs12",
+        "kdbx" => "application/x-keepass2",
+        "gpg" | "asc" => "application/pgp-encrypted",
+        "zip" => "application/zip",
+        "gz" | "tgz" => "application/gzip",
+        "pdf" => "application/pdf",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope_round_trip() {
+        let envelope = FileEnvelope::new("client.p12", b"\x30\x82\x04\x2c binary");
+        assert_eq!(envelope.mime_type, "application/x-pkcs12");
+
+        let secret = envelope.encode().unwrap();
+        let detected = FileEnvelope::detect(&secret).unwrap();
+        assert_eq!(detected.contents().unwrap(), b"\x30\x82\x04\x2c binary");
+
+        assert!(FileEnvelope::detect("just a password").is_none());
+        assert!(FileEnvelope::detect(r#"{"type":"something-else"}"#).is_none());
+
+        let mut tampered = detected;
+        tampered.data = BASE64.encode(b"other contents!!!!!!!");
+        assert!(tampered.contents().is_err());
+    }
+
+    #[test]
+    fn test_read_enforces_size_limit() {
+        let path = std::env::temp_dir().join(format!("ots-kubeconfig-{}.yaml", std::process::id()));
+        std::fs::write(&path, "x".repeat(600)).unwrap();
+
+        let envelope = FileEnvelope::read(&path, 2000).unwrap();
+        assert_eq!(envelope.size, 600);
+        assert_eq!(envelope.mime_type, "application/yaml");
+        assert!(FileEnvelope::read(&path, 700).is_err());
+        assert!(FileEnvelope::read(&path, 1000).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_archive_and_preview() {
+        let dir = std::env::temp_dir().join(format!("ots-archive-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("other")).unwrap();
+        let paths = vec![
+            dir.join(".env"),
+            dir.join("other").join(".env"),
+            dir.join("key.bin"),
+        ];
+        std::fs::write(&paths[0], "TOKEN=abc\n").unwrap();
+        std::fs::write(&paths[1], "TOKEN=def\n").unwrap();
+        std::fs::write(&paths[2], [0u8, 159, 146, 150]).unwrap();
+
+        let preview = FilePreview::read(&paths[0], 1000).unwrap();
+        assert_eq!(preview.preview.as_deref(), Some("TOKEN=abc\n"));
+        assert!(!preview.too_large);
+        assert!(FilePreview::read(&paths[2], 1000)
+            .unwrap()
+            .preview
+            .is_none());
+        assert!(FilePreview::read(&dir, 1000).is_err());
+
+        let envelope = FileEnvelope::archive(&paths, 10_000).unwrap();
+        assert_eq!(envelope.name, "shared-files.zip");
+        assert_eq!(envelope.mime_type, "application/zip");
+
+        let mut zip = zip::ZipArchive::new(Cursor::new(envelope.contents().unwrap())).unwrap();
+        let names: Vec<&str> = zip.file_names().collect();
+        assert_eq!(names.len(), 3);
+        assert!(names.contains(&".env (2)"));
+        let mut second = String::new();
+        zip.by_name(".env (2)")
+            .unwrap()
+            .read_to_string(&mut second)
+            .unwrap();
+        assert_eq!(second, "TOKEN=def\n");
+
+        // The files alone fit in 100 bytes but the archive does not, and
+        // both errors report the size of the files
+        for max_size in [24, 100] {
+            let err = FileEnvelope::archive(&paths, max_size).unwrap_err();
+            assert!(err.to_string().contains("File is 24 bytes"));
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_unique_name() {
+        let taken = vec!["a.txt".to_string(), "a (2).txt".to_string()];
+        assert_eq!(unique_name("b.txt", &taken), "b.txt");
+        assert_eq!(unique_name("a.txt", &taken), "a (3).txt");
+        assert_eq!(unique_name("id_rsa", &["id_rsa".to_string()]), "id_rsa (2)");
+    }
+
+    #[test]
+    fn test_safe_name() {
+        let named = |name: &str| FileEnvelope::new(name, b"");
+        assert_eq!(named("id_ed25519").safe_name(), "id_ed25519");
+        assert_eq!(
+            named("../../.ssh/authorized_keys").safe_name(),
+            "authorized_keys"
+        );
+        assert_eq!(named("C:\\Users\\x\\kubeconfig").safe_name(), "kubeconfig");
+        assert_eq!(named("..").safe_name(), "secret-file");
+        assert_eq!(named(".env").safe_name(), ".env");
+    }
+}
@@ -0,0 +1,135 @@
+use crate::error::{AppError, AppResult};
+use crate::history::{History, HistoryEntry};
+use serde::{Deserialize, Serialize};
+
+/// What to do with each selected receipt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchAction {
+    /// Burn the secret so it can no longer be viewed
+    Burn,
+    /// Look up the current state of the secret
+    Refresh,
+}
+
+/// Receipts from the history to act on
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "select", rename_all = "snake_case")]
+pub enum BatchSelection {
+    Keys {
+        metadata_keys: Vec<String>,
+    },
+    /// All receipts created together, e.g. a bulk creation or split secret
+    Group {
+        group: String,
+    },
+    /// Receipts that haven't expired or been viewed or burned
+    Pending,
+}
+
+impl BatchSelection {
+    pub fn entries(&self, history: &History) -> AppResult<Vec<HistoryEntry>> {
+        let entries = match self {
+            BatchSelection::Keys { metadata_keys } => metadata_keys
+                .iter()
+                .map(|key| {
+                    history.get(key).ok_or_else(|| {
+                        AppError::InvalidInput(format!("No receipt in the history for {}", key))
+                    })
+                })
+                .collect::<AppResult<_>>()?,
+            BatchSelection::Group { group } => history.group(group),
+            BatchSelection::Pending => history.pending(),
+        };
+
+        if entries.is_empty() {
+            return Err(AppError::InvalidInput("No receipts selected".to_string()));
+        }
+        Ok(entries)
+    }
+}
+
+/// Request to burn or refresh many receipts at once
+#[derive(Debug, Deserialize)]
+pub struct BatchRequest {
+    /// Operation ID chosen by the caller, to cancel the batch and match its
+    /// progress events
+    pub id: String,
+    pub action: BatchAction,
+    #[serde(flatten)]
+    pub selection: BatchSelection,
+    #[serde(default)]
+    pub concurrency: Option<usize>,
+}
+
+/// Outcome for one receipt
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchItem {
+    pub metadata_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl BatchItem {
+    /// Outcome of a receipt the batch stopped before
+    pub fn cancelled(metadata_key: &str) -> Self {
+        Self {
+            metadata_key: metadata_key.to_string(),
+            state: None,
+            error: Some("Cancelled".to_string()),
+        }
+    }
+}
+
+/// Result of a batch, in the order of the selection
+#[derive(Debug, Serialize)]
+pub struct BatchReport {
+    pub id: String,
+    pub action: BatchAction,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// Whether the batch was stopped before every receipt was processed
+    pub cancelled: bool,
+    pub items: Vec<BatchItem>,
+}
+
+impl BatchReport {
+    pub fn new(request: &BatchRequest, items: Vec<BatchItem>, cancelled: bool) -> Self {
+        let failed = items.iter().filter(|item| item.error.is_some()).count();
+        Self {
+            id: request.id.clone(),
+            action: request.action,
+            succeeded: items.len() - failed,
+            failed,
+            cancelled,
+            items,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_selection() {
+        let request: BatchRequest = serde_json::from_str(
+            r#"{"id": "op1", "action": "burn", "select": "group", "group": "batch-1"}"#,
+        )
+        .unwrap();
+        assert_eq!(request.action, BatchAction::Burn);
+        assert_eq!(
+            request.selection,
+            BatchSelection::Group {
+                group: "batch-1".to_string()
+            }
+        );
+
+        let request: BatchRequest =
+            serde_json::from_str(r#"{"id": "op2", "action": "refresh", "select": "pending"}"#)
+                .unwrap();
+        assert_eq!(request.selection, BatchSelection::Pending);
+    }
+}
@@ -0,0 +1,393 @@
+use crate::api::CreateSecretRequest;
+use crate::error::{AppError, AppResult};
+use crate::passphrase;
+use crate::store;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+/// Passphrase value asking for a random passphrase to be generated
+pub const GENERATE_PASSPHRASE: &str = "generate";
+
+/// Most rows one batch may create
+pub const MAX_ROWS: usize = 500;
+
+/// Requests at once unless the caller asks otherwise
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Most requests at once, to stay friendly with the API
+const MAX_CONCURRENCY: usize = 8;
+
+/// Attempts per call when the API keeps rate limiting
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Longest wait after a 429, whatever `Retry-After` asks for
+const MAX_BACKOFF_SECS: u64 = 60;
+
+/// Request to create one secret per CSV or JSON row
+///
+/// Rows come either as text or from a file. CSV needs a header line with
+/// `secret` and optionally `recipient`, `ttl` and `passphrase` columns; JSON
+/// is an array of objects with the same fields.
+#[derive(Debug, Deserialize)]
+pub struct BulkCreateRequest {
+    /// Operation ID, to cancel the batch and match its progress events
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub input: Option<String>,
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    #[serde(default)]
+    pub concurrency: Option<usize>,
+}
+
+impl BulkCreateRequest {
+    /// Parse the rows from the text or the file
+    pub fn rows(&self) -> AppResult<Vec<BulkRow>> {
+        let input = match (&self.input, &self.path) {
+            (Some(input), _) => input.clone(),
+            (None, Some(path)) => std::fs::read_to_string(path)?,
+            (None, None) => {
+                return Err(AppError::InvalidInput(
+                    "Provide the rows as text or a file".to_string(),
+                ))
+            }
+        };
+
+        parse(&input)
+    }
+
+    pub fn concurrency(&self) -> usize {
+        concurrency(self.concurrency)
+    }
+}
+
+/// Requests to run at once, the default unless asked for and never too many
+pub fn concurrency(requested: Option<usize>) -> usize {
+    requested
+        .unwrap_or(DEFAULT_CONCURRENCY)
+        .clamp(1, MAX_CONCURRENCY)
+}
+
+/// One secret to create
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BulkRow {
+    pub secret: String,
+    #[serde(default)]
+    pub recipient: Option<String>,
+    #[serde(default)]
+    pub ttl: Option<u32>,
+    /// A passphrase, or `generate` for a random one
+    #[serde(default)]
+    pub passphrase: Option<String>,
+}
+
+impl BulkRow {
+    /// Build the create request, returning the passphrase if one was generated
+    ///
+    /// Rows without a TTL use `default_ttl`; rows without a passphrase get a
+    /// generated one when `require_passphrase` is set.
+    pub fn into_request(
+        self,
+        default_ttl: u32,
+        require_passphrase: bool,
+    ) -> (CreateSecretRequest, Option<String>) {
+        let passphrase = self
+            .passphrase
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty());
+        let generated = match passphrase.as_deref() {
+            Some(p) if p.eq_ignore_ascii_case(GENERATE_PASSPHRASE) => Some(passphrase::generate()),
+            None if require_passphrase => Some(passphrase::generate()),
+            _ => None,
+        };
+
+        let request = CreateSecretRequest {
+            secret: self.secret,
+            passphrase: generated.clone().or(passphrase),
+            ttl: self.ttl.unwrap_or(default_ttl),
+            recipient: self
+                .recipient
+                .map(|r| r.trim().to_string())
+                .filter(|r| !r.is_empty()),
+        };
+
+        (request, generated)
+    }
+}
+
+/// Parse CSV or JSON rows, telling them apart by the first character
+pub fn parse(input: &str) -> AppResult<Vec<BulkRow>> {
+    let input = input.trim();
+    let rows: Vec<BulkRow> = if input.starts_with('[') {
+        serde_json::from_str(input)
+            .map_err(|e| AppError::InvalidInput(format!("Invalid JSON rows: {}", e)))?
+    } else {
+        csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(input.as_bytes())
+            .deserialize()
+            .enumerate()
+            .map(|(i, row)| {
+                // Line 1 is the header
+                row.map_err(|e| {
+                    AppError::InvalidInput(format!("Invalid CSV row on line {}: {}", i + 2, e))
+                })
+            })
+            .collect::<AppResult<_>>()?
+    };
+
+    if rows.is_empty() {
+        return Err(AppError::InvalidInput("No rows to create".to_string()));
+    }
+    if rows.len() > MAX_ROWS {
+        return Err(AppError::InvalidInput(format!(
+            "At most {} rows can be created at once",
+            MAX_ROWS
+        )));
+    }
+
+    Ok(rows)
+}
+
+/// Random ID of a batch, also the history group of its receipts
+pub fn batch_id() -> String {
+    format!("batch-{:016x}", rand::random::<u64>())
+}
+
+/// Result of one row, in the order of the input
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BulkRowResult {
+    /// Position of the row, from 1
+    pub row: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata_key: Option<String>,
+    /// Passphrase to hand over separately, if it was generated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generated_passphrase: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Per-row report of a bulk creation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkReport {
+    pub batch: String,
+    pub created: usize,
+    pub failed: usize,
+    /// Whether the batch was stopped before every row was created
+    #[serde(default)]
+    pub cancelled: bool,
+    pub rows: Vec<BulkRowResult>,
+}
+
+impl BulkReport {
+    pub fn new(batch: String, rows: Vec<BulkRowResult>, cancelled: bool) -> Self {
+        let failed = rows.iter().filter(|row| row.error.is_some()).count();
+        Self {
+            batch,
+            created: rows.len() - failed,
+            failed,
+            cancelled,
+            rows,
+        }
+    }
+
+    /// Write the report as JSON or, for any other extension, CSV
+    ///
+    /// The file holds links and generated passphrases, so it is only readable
+    /// by the user.
+    pub fn export(&self, path: &Path) -> AppResult<()> {
+        let contents = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::to_vec_pretty(self)?
+        } else {
+            self.to_csv()?
+        };
+
+        store::write_private(path, &contents)
+    }
+
+    fn to_csv(&self) -> AppResult<Vec<u8>> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer
+            .write_record([
+                "row",
+                "recipient",
+                "link",
+                "metadata_key",
+                "generated_passphrase",
+                "error",
+            ])
+            .map_err(csv_error)?;
+
+        for row in &self.rows {
+            writer
+                .write_record([
+                    row.row.to_string().as_str(),
+                    row.recipient.as_deref().unwrap_or_default(),
+                    row.link.as_deref().unwrap_or_default(),
+                    row.metadata_key.as_deref().unwrap_or_default(),
+                    row.generated_passphrase.as_deref().unwrap_or_default(),
+                    row.error.as_deref().unwrap_or_default(),
+                ])
+                .map_err(csv_error)?;
+        }
+
+        writer
+            .into_inner()
+            .map_err(|e| AppError::Storage(e.to_string()))
+    }
+}
+
+fn csv_error(e: csv::Error) -> AppError {
+    AppError::Storage(format!("Failed to write CSV report: {}", e))
+}
+
+/// Shared pause for all requests of a batch once the API rate limits one
+pub struct RateLimiter {
+    resume_at: Mutex<Option<Instant>>,
+    stopped: watch::Sender<bool>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self {
+            resume_at: Mutex::new(None),
+            stopped: watch::channel(false).0,
+        }
+    }
+
+    /// Start no more calls; calls in flight still finish
+    pub fn stop(&self) {
+        self.stopped.send_replace(true);
+    }
+
+    /// Run an API call, retrying it after 429s until `MAX_ATTEMPTS`
+    ///
+    /// Fails with `AppError::Cancelled` instead of starting a call once the
+    /// limiter is stopped, including while waiting out a pause.
+    pub async fn run<T, F, Fut>(&self, mut call: F) -> AppResult<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = AppResult<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            tokio::select! {
+                biased;
+                _ = self.stopped() => return Err(AppError::Cancelled),
+                _ = self.ready() => {}
+            }
+            match call().await {
+                Err(AppError::RateLimited(retry_after)) if attempt + 1 < MAX_ATTEMPTS => {
+                    self.pause(retry_after, attempt);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Resolves once the limiter is stopped
+    async fn stopped(&self) {
+        // The sender lives as long as `self`, so this only returns once stopped
+        let _ = self.stopped.subscribe().wait_for(|stopped| *stopped).await;
+    }
+
+    /// Wait until any pause has passed
+    async fn ready(&self) {
+        let resume_at = *self.resume_at.lock().unwrap();
+        if let Some(resume_at) = resume_at {
+            tokio::time::sleep_until(resume_at).await;
+        }
+    }
+
+    /// Pause all requests after a 429, honoring `Retry-After` or backing off
+    /// exponentially by attempt
+    fn pause(&self, retry_after: Option<u64>, attempt: u32) {
+        let secs = retry_after
+            .unwrap_or(1 << attempt.min(6))
+            .min(MAX_BACKOFF_SECS);
+        let until = Instant::now() + Duration::from_secs(secs);
+
+        let mut resume_at = self.resume_at.lock().unwrap();
+        if !resume_at.is_some_and(|current| current >= until) {
+            *resume_at = Some(until);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv_and_json() {
+        let csv = "secret,recipient,ttl,passphrase\n\
+                   \"pw, with comma\",alice@example.com,3600,generate\n\
+                   second,,,\n";
+        let rows = parse(csv).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].secret, "pw, with comma");
+        assert_eq!(rows[0].ttl, Some(3600));
+        assert_eq!(rows[1].ttl, None);
+
+        let json = r#"[{"secret": "a", "passphrase": "hunter2"}, {"secret": "b"}]"#;
+        let rows = parse(json).unwrap();
+        assert_eq!(rows[0].passphrase.as_deref(), Some("hunter2"));
+
+        assert!(parse("secret\n").is_err());
+        assert!(parse("recipient\nalice@example.com\n").is_err());
+    }
+
+    #[test]
+    fn test_into_request() {
+        let row = BulkRow {
+            secret: "s".to_string(),
+            recipient: Some(" ".to_string()),
+            ttl: None,
+            passphrase: Some("Generate".to_string()),
+        };
+        let (request, generated) = row.clone().into_request(3600, false);
+        assert_eq!(request.ttl, 3600);
+        assert_eq!(request.recipient, None);
+        assert!(generated.is_some());
+        assert_eq!(request.passphrase, generated);
+
+        let plain = BulkRow {
+            passphrase: None,
+            ..row
+        };
+        assert_eq!(plain.clone().into_request(60, false).0.passphrase, None);
+        assert!(plain.into_request(60, true).1.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_stopped_limiter_starts_no_calls() {
+        let limiter = RateLimiter::new();
+        limiter.pause(Some(MAX_BACKOFF_SECS), 0);
+        limiter.stop();
+
+        let calls = Mutex::new(0);
+        let result = tokio::time::timeout(
+            Duration::from_secs(1),
+            limiter.run(|| async {
+                *calls.lock().unwrap() += 1;
+                Ok(())
+            }),
+        )
+        .await
+        .unwrap();
+        assert!(matches!(result, Err(AppError::Cancelled)));
+        assert_eq!(*calls.lock().unwrap(), 0);
+    }
+}
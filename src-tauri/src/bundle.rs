@@ -0,0 +1,303 @@
+use crate::error::{AppError, AppResult};
+use crate::history::{self, HistoryEntry};
+use crate::incoming::RequestStatus;
+use crate::settings::AppSettings;
+use crate::storage::{ApiConfig, MAX_PROFILE_NAME_LENGTH};
+use age::secrecy::SecretString;
+use serde::{Deserialize, Serialize};
+
+/// Format version of the bundle contents
+const BUNDLE_VERSION: u32 = 1;
+
+/// Shortest accepted bundle passphrase
+const MIN_PASSPHRASE_LENGTH: usize = 8;
+
+/// Profiles, settings and history moved between installs
+///
+/// The bundle is serialized as JSON and encrypted with an age passphrase,
+/// so it can also be opened with the `age` command line tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bundle {
+    pub version: u32,
+    pub created_at: u64,
+    pub active_profile: String,
+    pub profiles: Vec<BundleProfile>,
+    pub settings: AppSettings,
+    pub history: Vec<HistoryEntry>,
+}
+
+/// A profile in a bundle, with its API key only when the user opted in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleProfile {
+    pub name: String,
+    pub config: ApiConfig,
+}
+
+impl Bundle {
+    pub fn new(
+        active_profile: String,
+        profiles: Vec<BundleProfile>,
+        settings: AppSettings,
+        history: Vec<HistoryEntry>,
+    ) -> Self {
+        Self {
+            version: BUNDLE_VERSION,
+            created_at: history::now(),
+            active_profile,
+            profiles,
+            settings,
+            history,
+        }
+    }
+
+    /// Whether any profile carries an API key
+    pub fn has_api_keys(&self) -> bool {
+        self.profiles.iter().any(|p| p.config.api_key.is_some())
+    }
+}
+
+/// Request to write a bundle to disk
+#[derive(Debug, Deserialize)]
+pub struct ExportRequest {
+    pub path: String,
+    pub passphrase: String,
+    /// Include API keys and other stored credentials, off unless the user opts in
+    #[serde(default)]
+    pub include_api_keys: bool,
+}
+
+/// Summary of a written bundle
+#[derive(Debug, Serialize)]
+pub struct ExportReport {
+    pub path: String,
+    pub profiles: Vec<String>,
+    pub history_entries: usize,
+    pub includes_api_keys: bool,
+}
+
+/// What to do with a bundle profile whose name already exists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// Keep the existing profile and ignore the bundled one
+    #[default]
+    Skip,
+    /// Replace the existing profile, keeping its credentials if none are
+    /// bundled and the base URL is unchanged
+    Overwrite,
+    /// Import the bundled profile under a new name such as `work-2`
+    Rename,
+}
+
+/// Request to read a bundle from disk
+#[derive(Debug, Deserialize)]
+pub struct ImportRequest {
+    pub path: String,
+    pub passphrase: String,
+    #[serde(default)]
+    pub on_conflict: ConflictStrategy,
+    /// Replace the local settings with the bundled ones
+    #[serde(default)]
+    pub import_settings: bool,
+}
+
+/// A bundle profile imported under a different name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RenamedProfile {
+    pub from: String,
+    pub to: String,
+}
+
+/// Summary of an import
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub imported: Vec<String>,
+    pub overwritten: Vec<String>,
+    pub renamed: Vec<RenamedProfile>,
+    pub skipped: Vec<String>,
+    pub history_entries: usize,
+    pub settings_imported: bool,
+}
+
+/// Serialize and encrypt a bundle with a passphrase
+pub fn seal(bundle: &Bundle, passphrase: &str) -> AppResult<Vec<u8>> {
+    validate_passphrase(passphrase)?;
+    let recipient = age::scrypt::Recipient::new(SecretString::from(passphrase.to_string()));
+    seal_to(bundle, &recipient)
+}
+
+fn seal_to(bundle: &Bundle, recipient: &age::scrypt::Recipient) -> AppResult<Vec<u8>> {
+    let json = serde_json::to_vec(bundle)?;
+    age::encrypt(recipient, &json)
+        .map_err(|e| AppError::Storage(format!("Failed to encrypt bundle: {}", e)))
+}
+
+/// Decrypt and parse a bundle
+pub fn open(data: &[u8], passphrase: &str) -> AppResult<Bundle> {
+    let identity = age::scrypt::Identity::new(SecretString::from(passphrase.to_string()));
+
+    let json = age::decrypt(&identity, data).map_err(|e| match e {
+        age::DecryptError::DecryptionFailed
+        | age::DecryptError::KeyDecryptionFailed
+        | age::DecryptError::NoMatchingKeys => {
+            AppError::InvalidInput("Wrong passphrase for this bundle".to_string())
+        }
+        e => AppError::InvalidInput(format!("Not a valid bundle file: {}", e)),
+    })?;
+
+    let bundle: Bundle = serde_json::from_slice(&json)?;
+    if bundle.version > BUNDLE_VERSION {
+        return Err(AppError::InvalidInput(format!(
+            "Bundle was exported by a newer version (v{}), please update the app",
+            bundle.version
+        )));
+    }
+
+    Ok(bundle)
+}
+
+/// First free name for a renamed profile, e.g. `work-2`
+pub fn free_name(name: &str, existing: &[String]) -> String {
+    (2..)
+        .map(|n| {
+            let suffix = format!("-{}", n);
+            let base: String = name
+                .chars()
+                .take(MAX_PROFILE_NAME_LENGTH - suffix.len())
+                .collect();
+            format!("{}{}", base, suffix)
+        })
+        .find(|candidate| !existing.contains(candidate))
+        .expect("unbounded range yields a free name")
+}
+
+/// Bundled history as it is merged into the local one
+///
+/// Entries of skipped profiles are dropped, as a local profile of the same
+/// name is a different account, and entries of renamed profiles follow the
+/// new name. Open requests are closed, as their keys stayed in the keychain
+/// they were created in.
+pub fn imported_history(history: Vec<HistoryEntry>, report: &ImportReport) -> Vec<HistoryEntry> {
+    history
+        .into_iter()
+        .filter(|entry| !report.skipped.contains(&entry.profile))
+        .map(|mut entry| {
+            if let Some(r) = report.renamed.iter().find(|r| r.from == entry.profile) {
+                entry.profile = r.to.clone();
+            }
+            if let Some(request) = entry
+                .request
+                .as_mut()
+                .filter(|request| request.status == RequestStatus::Open)
+            {
+                request.status = RequestStatus::Cancelled;
+                request.closed_at = Some(history::now());
+            }
+            entry
+        })
+        .collect()
+}
+
+fn validate_passphrase(passphrase: &str) -> AppResult<()> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LENGTH {
+        return Err(AppError::InvalidInput(format!(
+            "Bundle passphrase must be at least {} characters",
+            MIN_PASSPHRASE_LENGTH
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incoming::IncomingRequest;
+
+    fn bundle() -> Bundle {
+        Bundle::new(
+            "work".to_string(),
+            vec![BundleProfile {
+                name: "work".to_string(),
+                config: ApiConfig {
+                    base_url: "https://eu.onetimesecret.com".to_string(),
+                    username: "test@example.com".to_string(),
+                    api_key: Some("test-key".to_string()),
+                    ..Default::default()
+                },
+            }],
+            AppSettings::default(),
+            Vec::new(),
+        )
+    }
+
+    #[test]
+    fn test_seal_and_open() {
+        let passphrase = SecretString::from("correct horse".to_string());
+        let mut recipient = age::scrypt::Recipient::new(passphrase);
+        // Keep the test fast, the default work factor takes about a second
+        recipient.set_work_factor(2);
+
+        let sealed = seal_to(&bundle(), &recipient).unwrap();
+        assert!(!String::from_utf8_lossy(&sealed).contains("test-key"));
+
+        let opened = open(&sealed, "correct horse").unwrap();
+        assert_eq!(opened.active_profile, "work");
+        assert!(opened.has_api_keys());
+
+        assert!(open(&sealed, "wrong horse").is_err());
+        assert!(open(b"not a bundle", "correct horse").is_err());
+    }
+
+    #[test]
+    fn test_free_name() {
+        let existing = vec!["work".to_string(), "work-2".to_string()];
+        assert_eq!(free_name("work", &existing), "work-3");
+        assert_eq!(free_name("home", &existing), "home-2");
+        assert_eq!(free_name(&"x".repeat(32), &existing).len(), 32);
+    }
+
+    #[test]
+    fn test_imported_history() {
+        let entry = |profile: &str| HistoryEntry {
+            metadata_key: format!("{}-metadata", profile),
+            link: "https://eu.onetimesecret.com/secret/abc".to_string(),
+            profile: profile.to_string(),
+            ttl: 3600,
+            recipient: None,
+            created_at: 0,
+            group: None,
+            state: None,
+            request: None,
+        };
+        let mut request = entry("home");
+        request.request = Some(IncomingRequest {
+            id: "request-1".to_string(),
+            public_key: "age1test".to_string(),
+            note: None,
+            status: RequestStatus::Open,
+            closed_at: None,
+        });
+        let report = ImportReport {
+            renamed: vec![RenamedProfile {
+                from: "work".to_string(),
+                to: "work-2".to_string(),
+            }],
+            skipped: vec!["old".to_string()],
+            ..Default::default()
+        };
+
+        let history = imported_history(vec![entry("work"), entry("old"), request], &report);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].profile, "work-2");
+        assert_eq!(history[1].profile, "home");
+        let request = history[1].request.as_ref().unwrap();
+        assert_eq!(request.status, RequestStatus::Cancelled);
+        assert!(request.closed_at.is_some());
+    }
+
+    #[test]
+    fn test_passphrase_length() {
+        assert!(seal(&bundle(), "short").is_err());
+    }
+}
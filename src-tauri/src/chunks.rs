@@ -0,0 +1,224 @@
+use crate::error::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Marks a secret as the manifest of a payload split across secrets
+pub const MANIFEST_TYPE: &str = "onetimesecret/chunked";
+
+/// Manifest format written by this build
+const MANIFEST_VERSION: u32 = 1;
+
+/// Most secrets a payload may be split into
+const MAX_CHUNKS: usize = 100;
+
+/// Room taken in the manifest by its own fields and by each chunk entry,
+/// as the manifest has to fit in a single secret too
+const MANIFEST_HEADER_SIZE: usize = 200;
+const CHUNK_ENTRY_SIZE: usize = 200;
+
+/// Integrity manifest of a payload shared as several secrets
+///
+/// The manifest is itself a secret, and its link is the one handed out.
+/// Only the manifest knows the chunk keys, so the chunks can't be found
+/// without it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub version: u32,
+    /// Length of the whole payload in bytes
+    pub length: u64,
+    /// Hex SHA-256 of the whole payload
+    pub sha256: String,
+    /// Chunks in payload order
+    pub chunks: Vec<Chunk>,
+}
+
+/// One secret holding part of a payload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Chunk {
+    pub index: usize,
+    pub key: String,
+    pub length: u64,
+    pub sha256: String,
+}
+
+impl Manifest {
+    /// Describe a payload and the keys of the secrets holding its parts
+    pub fn new(payload: &str, parts: &[&str], keys: Vec<String>) -> Self {
+        let chunks = parts
+            .iter()
+            .zip(keys)
+            .enumerate()
+            .map(|(index, (part, key))| Chunk {
+                index,
+                key,
+                length: part.len() as u64,
+                sha256: hex_digest(part.as_bytes()),
+            })
+            .collect();
+
+        Self {
+            kind: MANIFEST_TYPE.to_string(),
+            version: MANIFEST_VERSION,
+            length: payload.len() as u64,
+            sha256: hex_digest(payload.as_bytes()),
+            chunks,
+        }
+    }
+
+    pub fn encode(&self) -> AppResult<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// The manifest in a retrieved secret, or None for anything else
+    pub fn detect(secret: &str) -> Option<Self> {
+        if !secret.trim_start().starts_with('{') {
+            return None;
+        }
+
+        serde_json::from_str::<Self>(secret)
+            .ok()
+            .filter(|manifest| manifest.kind == MANIFEST_TYPE)
+    }
+
+    /// Fail early for manifests this build can't reassemble
+    pub fn check(&self) -> AppResult<()> {
+        if self.version > MANIFEST_VERSION {
+            return Err(AppError::InvalidInput(format!(
+                "Secret was split by a newer version (v{}), please update the app",
+                self.version
+            )));
+        }
+
+        let in_order = self.chunks.iter().enumerate().all(|(i, c)| c.index == i);
+        if self.chunks.is_empty() || self.chunks.len() > MAX_CHUNKS || !in_order {
+            return Err(AppError::InvalidInput(
+                "Chunk manifest is damaged".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Put the retrieved chunks back together, checking every hash
+    pub fn reassemble(&self, parts: &[String]) -> AppResult<String> {
+        if parts.len() != self.chunks.len() {
+            return Err(AppError::InvalidInput(format!(
+                "Expected {} chunks, got {}",
+                self.chunks.len(),
+                parts.len()
+            )));
+        }
+
+        for (chunk, part) in self.chunks.iter().zip(parts) {
+            if part.len() as u64 != chunk.length || hex_digest(part.as_bytes()) != chunk.sha256 {
+                return Err(AppError::InvalidInput(format!(
+                    "Chunk {} of {} does not match its checksum",
+                    chunk.index + 1,
+                    self.chunks.len()
+                )));
+            }
+        }
+
+        let payload = parts.concat();
+        if payload.len() as u64 != self.length || hex_digest(payload.as_bytes()) != self.sha256 {
+            return Err(AppError::InvalidInput(
+                "Reassembled payload does not match its checksum".to_string(),
+            ));
+        }
+
+        Ok(payload)
+    }
+}
+
+/// Split a payload into parts of at most `max_size` bytes
+///
+/// Parts end on character boundaries, since each one travels as a text
+/// secret. Fails if the manifest for the parts would not fit in a secret.
+pub fn split(payload: &str, max_size: usize) -> AppResult<Vec<&str>> {
+    let mut parts = Vec::new();
+    let mut rest = payload;
+
+    while !rest.is_empty() {
+        let mut end = max_size.min(rest.len());
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        let (part, tail) = rest.split_at(end);
+        parts.push(part);
+        rest = tail;
+    }
+
+    if parts.len() > max_chunks(max_size) {
+        return Err(AppError::InvalidInput(format!(
+            "Payload is {} bytes, at most about {} bytes can be split across secrets",
+            payload.len(),
+            max_payload_size(max_size)
+        )));
+    }
+
+    Ok(parts)
+}
+
+/// Largest payload that can be split across secrets of `max_size` bytes
+pub fn max_payload_size(max_size: usize) -> usize {
+    max_size * max_chunks(max_size)
+}
+
+/// Most chunks whose manifest still fits in `max_size` bytes
+fn max_chunks(max_size: usize) -> usize {
+    (max_size.saturating_sub(MANIFEST_HEADER_SIZE) / CHUNK_ENTRY_SIZE).clamp(1, MAX_CHUNKS)
+}
+
+fn hex_digest(contents: &[u8]) -> String {
+    Sha256::digest(contents)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_and_reassemble() {
+        let payload: String = (0..200).map(|i| format!("ключ{}=", i)).collect();
+        let parts = split(&payload, 1000).unwrap();
+        assert_eq!(parts.len(), 3);
+        assert!(parts.iter().all(|part| part.len() <= 1000));
+
+        let keys = (0..parts.len()).map(|i| format!("key{}", i)).collect();
+        let manifest = Manifest::new(&payload, &parts, keys);
+        let detected = Manifest::detect(&manifest.encode().unwrap()).unwrap();
+        detected.check().unwrap();
+
+        let retrieved: Vec<String> = parts.iter().map(|part| part.to_string()).collect();
+        assert_eq!(detected.reassemble(&retrieved).unwrap(), payload);
+
+        let mut swapped = retrieved.clone();
+        swapped.swap(0, 1);
+        assert!(detected.reassemble(&swapped).is_err());
+        assert!(detected.reassemble(&retrieved[1..]).is_err());
+
+        assert!(Manifest::detect("plain secret").is_none());
+        // A manifest of five entries would not fit in 1000 bytes
+        assert_eq!(max_payload_size(1000), 4000);
+        assert!(split(&"x".repeat(4001), 1000).is_err());
+        assert_eq!(max_payload_size(100_000), 100_000 * MAX_CHUNKS);
+    }
+
+    #[test]
+    fn test_check_rejects_damaged_manifest() {
+        let mut manifest = Manifest::new("ab", &["a", "b"], vec!["k1".into(), "k2".into()]);
+        manifest.check().unwrap();
+
+        manifest.chunks.swap(0, 1);
+        assert!(manifest.check().is_err());
+
+        manifest.chunks.clear();
+        assert!(manifest.check().is_err());
+    }
+}
@@ -0,0 +1,190 @@
+use crate::error::{AppError, AppResult};
+use arboard::Clipboard;
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Default delay before copied values are cleared, until changed in settings
+pub const DEFAULT_CLEAR_AFTER_SECS: u64 = 30;
+
+/// Clipboard access with automatic clearing of sensitive values
+///
+/// Every copy bumps a generation counter so that only the most recent
+/// copy's timer is allowed to clear the clipboard. The timer also checks
+/// that the clipboard still holds our value before clearing, so anything
+/// the user copied in the meantime is left alone.
+#[derive(Default)]
+pub struct ClipboardManager {
+    generation: Arc<AtomicU64>,
+}
+
+impl ClipboardManager {
+    /// Copy text to the clipboard, marked as sensitive where supported
+    ///
+    /// When `clear_after` is set, the clipboard is cleared once the delay
+    /// elapses, provided it still holds `text`.
+    pub fn copy(&self, text: &str, clear_after: Option<Duration>) -> AppResult<()> {
+        if text.is_empty() {
+            return Err(AppError::InvalidInput(
+                "Nothing to copy to the clipboard".to_string(),
+            ));
+        }
+
+        write_sensitive(text)?;
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+
+        if let Some(delay) = clear_after {
+            let digest = digest(text);
+            let current = Arc::clone(&self.generation);
+
+            tauri::async_runtime::spawn(async move {
+                tokio::time::sleep(delay).await;
+
+                // A newer copy owns the clipboard now
+                if current.load(Ordering::SeqCst) != generation {
+                    return;
+                }
+
+                let result = tokio::task::spawn_blocking(move || clear_if_matches(&digest)).await;
+                match result {
+                    Ok(Ok(true)) => log::info!("Cleared sensitive value from clipboard"),
+                    Ok(Ok(false)) => {}
+                    Ok(Err(e)) => log::warn!("Failed to clear clipboard: {}", e),
+                    Err(e) => log::warn!("Clipboard clear task failed: {}", e),
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Clear the clipboard immediately and cancel any pending clear
+    pub fn clear(&self) -> AppResult<()> {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+
+        let mut clipboard = open()?;
+        clipboard.clear().map_err(clipboard_error)
+    }
+}
+
+/// Read the current clipboard text
+pub fn read_text() -> AppResult<String> {
+    let text = open()?.get_text().map_err(|e| match e {
+        arboard::Error::ContentNotAvailable => {
+            AppError::InvalidInput("Clipboard does not contain text".to_string())
+        }
+        e => clipboard_error(e),
+    })?;
+
+    if text.trim().is_empty() {
+        return Err(AppError::InvalidInput("Clipboard is empty".to_string()));
+    }
+
+    Ok(text)
+}
+
+/// Convert a clear timeout in seconds to a delay, where zero disables clearing
+pub fn clear_delay(clear_after_secs: u64) -> Option<Duration> {
+    match clear_after_secs {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    }
+}
+
+fn open() -> AppResult<Clipboard> {
+    Clipboard::new().map_err(clipboard_error)
+}
+
+fn clipboard_error(err: arboard::Error) -> AppError {
+    AppError::Clipboard(err.to_string())
+}
+
+fn digest(text: &str) -> [u8; 32] {
+    Sha256::digest(text.as_bytes()).into()
+}
+
+/// Write text, asking the platform to keep it out of clipboard history
+fn write_sensitive(text: &str) -> AppResult<()> {
+    let mut clipboard = open()?;
+
+    #[cfg(target_os = "windows")]
+    {
+        use arboard::SetExtWindows;
+        clipboard
+            .set()
+            .exclude_from_monitoring()
+            .exclude_from_cloud()
+            .exclude_from_history()
+            .text(text)
+            .map_err(clipboard_error)
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        use arboard::SetExtApple;
+        clipboard
+            .set()
+            .exclude_from_history()
+            .text(text)
+            .map_err(clipboard_error)
+    }
+
+    #[cfg(all(
+        unix,
+        not(any(target_os = "macos", target_os = "android", target_os = "ios"))
+    ))]
+    {
+        use arboard::SetExtLinux;
+        clipboard
+            .set()
+            .exclude_from_history()
+            .text(text)
+            .map_err(clipboard_error)
+    }
+
+    #[cfg(not(any(
+        target_os = "windows",
+        target_os = "macos",
+        all(unix, not(any(target_os = "android", target_os = "ios")))
+    )))]
+    {
+        clipboard.set_text(text).map_err(clipboard_error)
+    }
+}
+
+/// Clear the clipboard only if it still holds the value with this digest
+fn clear_if_matches(expected: &[u8; 32]) -> AppResult<bool> {
+    let mut clipboard = open()?;
+
+    let current = match clipboard.get_text() {
+        Ok(text) => text,
+        // Clipboard is empty or holds non-text content we didn't put there
+        Err(arboard::Error::ContentNotAvailable) => return Ok(false),
+        Err(e) => return Err(clipboard_error(e)),
+    };
+
+    if digest(&current) != *expected {
+        return Ok(false);
+    }
+
+    clipboard.clear().map_err(clipboard_error)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clear_delay() {
+        assert_eq!(clear_delay(10), Some(Duration::from_secs(10)));
+        assert_eq!(clear_delay(0), None);
+    }
+
+    #[test]
+    fn test_digest_distinguishes_values() {
+        assert_eq!(digest("secret"), digest("secret"));
+        assert_ne!(digest("secret"), digest("secret "));
+    }
+}
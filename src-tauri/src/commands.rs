@@ -1,7 +1,43 @@
 use crate::api::{CreateSecretRequest, CreateSecretResponse, OtsClient, RetrieveSecretRequest, RetrieveSecretResponse};
+use crate::attachment::{
+    FileEnvelope, FilePreview, ReceivedFile, ReceivedFiles, ShareFileRequest, ShareFilesRequest,
+    ShareMode,
+};
+use crate::batch::{BatchAction, BatchItem, BatchReport, BatchRequest};
+use crate::bulk::{self, BulkCreateRequest, BulkReport, BulkRowResult, RateLimiter};
+use crate::bundle::{
+    self, Bundle, BundleProfile, ConflictStrategy, ExportReport, ExportRequest, ImportReport,
+    ImportRequest, RenamedProfile,
+};
+use crate::chunks::{self, Manifest};
+use crate::clipboard::{self, ClipboardManager};
+use crate::csp;
+use crate::deep_link::{self, DeepLinkAction, DeepLinkQueue};
+use crate::encryption::{self, OwnKeys};
 use crate::error::{AppError, AppResult};
-use crate::storage::{ApiConfig, SecureStorage};
+use crate::history::{History, HistoryEntry};
+use crate::hotkeys::{self, HotkeySettings};
+use crate::incoming::{
+    self, IncomingRequest, NewIncomingRequest, ReceivedRequest, RequestAnswer, RequestStatus,
+};
+use crate::instance::PendingFiles;
+use crate::operations::{self, Operation, Operations};
+use crate::qr::{self, QrImage, QrRequest};
+use crate::recipients::{KeyKind, RecipientKey, Recipients};
+use crate::resolve::{self, ConfigReport, ResolvedConfig};
+use crate::settings::{AppSettings, SettingsStore};
+use crate::shamir::{self, SplitSecretRequest};
+use crate::storage::{ApiConfig, ProfileIndex, SecureStorage};
+use crate::store;
+use crate::templates::{self, SecretTemplate, TemplateInput, TemplatedSecret, Templates};
+use crate::tray;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 
 /// Test connection result
 #[derive(Debug, Serialize, Deserialize)]
@@ -9,6 +45,131 @@ pub struct TestConnectionResult {
     pub success: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure: Option<ConnectionFailure>,
+}
+
+/// A retrieved secret, with files kept back for saving instead of shown as text
+#[derive(Debug, Serialize)]
+pub struct RetrievedSecret {
+    #[serde(flatten)]
+    pub response: RetrieveSecretResponse,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<ReceivedFile>,
+    /// Number of secrets the payload was reassembled from, if it was split
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunks: Option<usize>,
+    /// Kind of the private key that decrypted the secret, if it was encrypted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decrypted_with: Option<KeyKind>,
+    /// Why an encrypted secret could not be decrypted; `secret` then holds
+    /// the ciphertext, as the secret is burned and this is the only copy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decrypt_error: Option<String>,
+    /// Labeled fields, if the secret was rendered from a template
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<TemplatedSecret>,
+    /// Someone's request for a secret, to be answered with `answer_incoming_request`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request: Option<ReceivedRequest>,
+    /// ID of the user's own request this secret answered and closed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub answered_request: Option<String>,
+}
+
+/// A created secret, with the passphrase a template rule generated for it
+#[derive(Debug, Serialize)]
+pub struct CreatedSecret {
+    #[serde(flatten)]
+    pub response: CreateSecretResponse,
+    /// To be sent to the recipient separately
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generated_passphrase: Option<String>,
+}
+
+/// A secret that may have been split across several linked secrets
+#[derive(Debug, Serialize)]
+pub struct ChunkedSecret {
+    /// The secret holding the manifest, or the payload if it fit in one
+    #[serde(flatten)]
+    pub response: CreateSecretResponse,
+    pub chunks: usize,
+}
+
+/// A secret created from one or more shared files
+#[derive(Debug, Serialize)]
+pub struct SharedFile {
+    /// File name, or the archive name when several files were combined
+    pub name: String,
+    #[serde(flatten)]
+    pub response: CreateSecretResponse,
+}
+
+/// Shares of a split secret, each created as its own secret
+#[derive(Debug, Serialize)]
+pub struct SplitSecret {
+    /// ID of the share set, also the history group of the receipts
+    pub set: String,
+    pub threshold: u8,
+    pub shares: Vec<SplitShare>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SplitShare {
+    /// Position of the share, from 1
+    pub index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<String>,
+    #[serde(flatten)]
+    pub response: CreateSecretResponse,
+}
+
+/// Outcome of combining shares
+#[derive(Debug, Serialize)]
+pub struct CombinedShares {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    /// Why the secret could not be rebuilt after links were retrieved
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// With `error`, the inputs with each retrieved link replaced by what it
+    /// held, as the linked secrets are burned and this is the only copy
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub inputs: Vec<String>,
+}
+
+/// Which part of a failed connection test went wrong
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionFailure {
+    /// The proxy could not be reached or refused the connection
+    Proxy,
+    /// The server's certificate chain was rejected or did not match a pin
+    Certificate,
+    /// The TLS handshake failed, e.g. the client certificate was refused
+    Handshake,
+    /// The API server could not be reached or returned an error
+    Target,
+    /// The API or the proxy rejected the credentials
+    Authentication,
+    /// The profile is missing or has invalid settings
+    Configuration,
+}
+
+impl ConnectionFailure {
+    fn of(err: &AppError) -> Self {
+        match err {
+            AppError::Proxy(_) => ConnectionFailure::Proxy,
+            AppError::Certificate(_) => ConnectionFailure::Certificate,
+            AppError::Handshake(_) => ConnectionFailure::Handshake,
+            AppError::Authentication(_) => ConnectionFailure::Authentication,
+            AppError::Configuration(_)
+            | AppError::CorruptedConfig(_)
+            | AppError::InvalidInput(_)
+            | AppError::Storage(_) => ConnectionFailure::Configuration,
+            _ => ConnectionFailure::Target,
+        }
+    }
 }
 
 /// Load API configuration from secure storage
@@ -20,10 +181,22 @@ pub async fn load_api_config() -> Result<Option<ApiConfig>, String> {
 
 /// Save API configuration to secure storage
 /// API key is stored in platform-specific keychain
+/// Saves to the active profile unless `profile` names another one
 #[tauri::command]
-pub async fn save_api_config(config: ApiConfig) -> Result<(), String> {
-    SecureStorage::save_config(&config)
-        .map_err(|e| e.to_string())
+pub async fn save_api_config(
+    app: AppHandle,
+    config: ApiConfig,
+    profile: Option<String>,
+) -> Result<(), String> {
+    match profile {
+        Some(profile) => SecureStorage::save_profile(&profile, &config),
+        None => SecureStorage::save_config(&config),
+    }
+    .map_err(|e| e.to_string())?;
+
+    tray::refresh_menu(&app);
+    csp::refresh(&app);
+    Ok(())
 }
 
 /// Test API connection and authentication
@@ -33,9 +206,11 @@ pub async fn test_api_connection() -> Result<TestConnectionResult, String> {
         Ok(success) => Ok(TestConnectionResult {
             success,
             error: None,
+            failure: (!success).then_some(ConnectionFailure::Target),
         }),
         Err(e) => Ok(TestConnectionResult {
             success: false,
+            failure: Some(ConnectionFailure::of(&e)),
             error: Some(e.to_string()),
         }),
     }
@@ -47,33 +222,746 @@ async fn test_connection_internal() -> AppResult<bool> {
 }
 
 /// Create a new secret
+/// With a template, the secret is rendered from its field values instead
 #[tauri::command]
-pub async fn create_secret(request: CreateSecretRequest) -> Result<CreateSecretResponse, String> {
-    create_secret_internal(request)
+pub async fn create_secret(
+    app: AppHandle,
+    request: CreateSecretRequest,
+    encrypt_to: Option<String>,
+    template: Option<TemplateInput>,
+    recipients: State<'_, Recipients>,
+    templates: State<'_, Templates>,
+) -> Result<CreatedSecret, String> {
+    let (request, generated_passphrase) = match &template {
+        Some(input) => templates.apply(input, request),
+        None => Ok((request, None)),
+    }
+    .map_err(|e| e.to_string())?;
+
+    let request =
+        seal_for(&recipients, request, encrypt_to.as_deref()).map_err(|e| e.to_string())?;
+    let response = share_secret(&app, &request)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(CreatedSecret {
+        response,
+        generated_passphrase,
+    })
+}
+
+/// Encrypt the secret to a known recipient's public key, if one is chosen
+fn seal_for(
+    recipients: &Recipients,
+    mut request: CreateSecretRequest,
+    encrypt_to: Option<&str>,
+) -> AppResult<CreateSecretRequest> {
+    if let Some(name) = encrypt_to {
+        request.secret = encryption::encrypt(&recipients.get(name)?, &request.secret)?;
+    }
+
+    Ok(request)
+}
+
+/// Create a secret with the active profile and record its receipt
+pub(crate) async fn share_secret(
+    app: &AppHandle,
+    request: &CreateSecretRequest,
+) -> AppResult<CreateSecretResponse> {
+    share_secret_in(app, request, None).await
+}
+
+/// Create a secret and record its receipt under a history group
+pub(crate) async fn share_secret_in(
+    app: &AppHandle,
+    request: &CreateSecretRequest,
+    group: Option<&str>,
+) -> AppResult<CreateSecretResponse> {
+    let profile = SecureStorage::profile_index()?.active;
+    let client = OtsClient::from_config().await?;
+    let response = client.create_secret(request).await?;
+
+    record_receipt(app, &profile, &response, request, group);
+    tray::refresh_menu(app);
+
+    Ok(response)
+}
+
+fn record_receipt(
+    app: &AppHandle,
+    profile: &str,
+    response: &CreateSecretResponse,
+    request: &CreateSecretRequest,
+    group: Option<&str>,
+) {
+    let entry = HistoryEntry::new(profile, response, request.ttl, request.recipient.clone())
+        .with_group(group);
+    if let Err(e) = app.state::<History>().record(entry) {
+        // The secret exists either way, so don't fail the share
+        log::warn!("Failed to record history: {}", e);
+    }
+}
+
+/// Create a secret, splitting it across several secrets if it is too large
+/// The returned link opens the manifest, which lists the other secrets.
+/// Runs as operation `id`; once cancelled, no more chunks are created and
+/// those already created are burned
+#[tauri::command]
+pub async fn create_chunked_secret(
+    app: AppHandle,
+    request: CreateSecretRequest,
+    encrypt_to: Option<String>,
+    id: Option<String>,
+    settings: State<'_, SettingsStore>,
+    recipients: State<'_, Recipients>,
+) -> Result<ChunkedSecret, String> {
+    let request =
+        seal_for(&recipients, request, encrypt_to.as_deref()).map_err(|e| e.to_string())?;
+    let max_size = settings.get().local_max_secret_size;
+    let handle = app.clone();
+    operations::run(&app, id, move |operation| async move {
+        share_chunked(&handle, &request, max_size, Some(&operation)).await
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Share a payload as one secret, or as chunks plus a manifest if it is
+/// larger than `max_size`
+///
+/// Only the manifest is recorded in the history; the chunk links are known
+/// to nothing but the manifest. If a chunk or the manifest fails, or
+/// `operation` is cancelled between chunks, the chunks already created are
+/// burned rather than left behind.
+pub(crate) async fn share_chunked(
+    app: &AppHandle,
+    request: &CreateSecretRequest,
+    max_size: usize,
+    operation: Option<&Operation>,
+) -> AppResult<ChunkedSecret> {
+    if request.secret.len() <= max_size {
+        let response = share_secret(app, request).await?;
+        return Ok(ChunkedSecret {
+            response,
+            chunks: 1,
+        });
+    }
+
+    let parts = chunks::split(&request.secret, max_size)?;
+    let client = OtsClient::from_config().await?;
+    let mut created = Vec::with_capacity(parts.len());
+    let shared = async {
+        for part in &parts {
+            if operation.is_some_and(Operation::is_cancelled) {
+                return Err(AppError::Cancelled);
+            }
+
+            // The recipient is notified once, about the manifest
+            let chunk = CreateSecretRequest {
+                secret: part.to_string(),
+                passphrase: request.passphrase.clone(),
+                ttl: request.ttl,
+                recipient: None,
+            };
+            created.push(client.create_secret(&chunk).await?);
+        }
+
+        let keys = created.iter().map(|c| c.secret_key.clone()).collect();
+        let manifest = Manifest::new(&request.secret, &parts, keys);
+        let secret = CreateSecretRequest {
+            secret: manifest.encode()?,
+            ..request.clone()
+        };
+        share_secret(app, &secret).await
+    }
+    .await;
+
+    match shared {
+        Ok(response) => Ok(ChunkedSecret {
+            response,
+            chunks: parts.len(),
+        }),
+        Err(e) => {
+            burn_created(&client, &created).await;
+            Err(e)
+        }
+    }
+}
+
+/// Burn the secrets of a share that failed part way, so they don't linger
+/// until they expire
+///
+/// Returns the metadata keys of the secrets that were burned.
+async fn burn_created<'a>(
+    client: &OtsClient,
+    created: impl IntoIterator<Item = &'a CreateSecretResponse>,
+) -> Vec<String> {
+    let mut burned = Vec::new();
+    for secret in created {
+        match client.burn_secret(&secret.metadata_key).await {
+            Ok(_) => burned.push(secret.metadata_key.clone()),
+            Err(e) => log::warn!("Failed to burn secret {}: {}", secret.metadata_key, e),
+        }
+    }
+    burned
+}
+
+/// Split a secret into M-of-N Shamir shares, one secret per share
+/// Receipts are recorded under the returned set ID; if a share fails, those
+/// already created are burned
+#[tauri::command]
+pub async fn split_secret(
+    app: AppHandle,
+    request: SplitSecretRequest,
+) -> Result<SplitSecret, String> {
+    split_secret_internal(&app, request)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn split_secret_internal(
+    app: &AppHandle,
+    request: SplitSecretRequest,
+) -> AppResult<SplitSecret> {
+    if request.recipients.len() > usize::from(request.shares) {
+        return Err(AppError::InvalidInput(
+            "More recipients than shares".to_string(),
+        ));
+    }
+
+    let (set, shares) = shamir::split(&request.secret, request.threshold, request.shares)?;
+    let client = OtsClient::from_config().await?;
+
+    let mut created = Vec::with_capacity(shares.len());
+    let shared = async {
+        for (index, share) in shares.into_iter().enumerate() {
+            let recipient = request
+                .recipients
+                .get(index)
+                .map(|r| r.trim().to_string())
+                .filter(|r| !r.is_empty());
+            let secret = CreateSecretRequest {
+                secret: share,
+                passphrase: request.passphrase.clone(),
+                ttl: request.ttl,
+                recipient: recipient.clone(),
+            };
+
+            let response = share_secret_in(app, &secret, Some(&set)).await?;
+            created.push(SplitShare {
+                index: index + 1,
+                recipient,
+                response,
+            });
+        }
+        Ok(())
+    }
+    .await;
+
+    if let Err(e) = shared {
+        let burned = burn_created(&client, created.iter().map(|share| &share.response)).await;
+        let states: Vec<_> = burned
+            .into_iter()
+            .map(|key| (key, "burned".to_string()))
+            .collect();
+        if let Err(e) = app.state::<History>().set_states(&states) {
+            log::warn!("Failed to record burned shares: {}", e);
+        }
+        tray::refresh_menu(app);
+        return Err(e);
+    }
+
+    Ok(SplitSecret {
+        set,
+        threshold: request.threshold,
+        shares: created,
+    })
+}
+
+/// Rebuild a secret from Shamir shares, pasted as text or as secret links
+/// Links are retrieved (and burned) only after every input was recognized
+/// and there are enough of them. If the secret can't be rebuilt after that,
+/// the result carries the error and the retrieved shares instead.
+#[tauri::command]
+pub async fn combine_shares(
+    inputs: Vec<String>,
+    passphrase: Option<String>,
+) -> Result<CombinedShares, String> {
+    combine_shares_internal(inputs, passphrase)
         .await
         .map_err(|e| e.to_string())
 }
 
-async fn create_secret_internal(request: CreateSecretRequest) -> AppResult<CreateSecretResponse> {
+async fn combine_shares_internal(
+    inputs: Vec<String>,
+    passphrase: Option<String>,
+) -> AppResult<CombinedShares> {
+    let base_url = resolve::api_config()?.map(|config| config.base_url);
+
+    let mut inputs: Vec<String> = inputs
+        .iter()
+        .map(|input| input.trim())
+        .filter(|input| !input.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    let mut links = Vec::new();
+    for (index, input) in inputs.iter().enumerate() {
+        if !shamir::is_share(input) {
+            links.push((index, secret_key_of(input, base_url.as_deref())?));
+        }
+    }
+    if let Some(share) = inputs.iter().find(|input| shamir::is_share(input)) {
+        shamir::check_count(share, inputs.len())?;
+    }
+
+    if links.is_empty() {
+        return Ok(CombinedShares {
+            secret: Some(shamir::combine(&inputs)?),
+            error: None,
+            inputs: Vec::new(),
+        });
+    }
+
     let client = OtsClient::from_config().await?;
-    client.create_secret(&request).await
+    let mut retrieved = 0;
+    let retrieval = async {
+        for (index, key) in links {
+            let request = RetrieveSecretRequest {
+                key,
+                passphrase: passphrase.clone(),
+            };
+            inputs[index] = client.retrieve_secret(&request).await?.secret;
+            retrieved += 1;
+
+            if !shamir::is_share(&inputs[index]) {
+                return Err(AppError::InvalidInput(
+                    "A linked secret does not hold a share".to_string(),
+                ));
+            }
+            // Without pasted shares, the first retrieved one tells how many
+            // are needed
+            shamir::check_count(&inputs[index], inputs.len())?;
+        }
+        Ok(())
+    }
+    .await;
+
+    match retrieval.and_then(|_| shamir::combine(&inputs)) {
+        Ok(secret) => Ok(CombinedShares {
+            secret: Some(secret),
+            error: None,
+            inputs: Vec::new(),
+        }),
+        Err(e) if retrieved == 0 => Err(e),
+        Err(e) => Ok(CombinedShares {
+            secret: None,
+            error: Some(e.to_string()),
+            inputs,
+        }),
+    }
+}
+
+/// Secret key from an app or web link to a secret
+fn secret_key_of(link: &str, base_url: Option<&str>) -> AppResult<String> {
+    let action = if link.starts_with(&format!("{}:", deep_link::SCHEME)) {
+        deep_link::parse(link)?
+    } else {
+        let base_url = base_url
+            .ok_or_else(|| AppError::Configuration("No API configuration found".to_string()))?;
+        deep_link::parse_web_link(link, base_url)?
+    };
+
+    match action {
+        DeepLinkAction::Retrieve { key } => Ok(key),
+        _ => Err(AppError::InvalidInput(
+            "Expected a share or a link to a secret".to_string(),
+        )),
+    }
+}
+
+/// Create one secret per CSV or JSON row with bounded concurrency
+/// Receipts are recorded under the returned batch ID; a failed row doesn't
+/// stop the others. Runs as operation `request.id`, emitting progress per row
+#[tauri::command]
+pub async fn bulk_create(app: AppHandle, request: BulkCreateRequest) -> Result<BulkReport, String> {
+    let id = request.id.clone();
+    let handle = app.clone();
+    operations::run(&app, id, move |operation| async move {
+        bulk_create_internal(&handle, request, &operation).await
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+async fn bulk_create_internal(
+    app: &AppHandle,
+    request: BulkCreateRequest,
+    operation: &Operation,
+) -> AppResult<BulkReport> {
+    let rows = request.rows()?;
+    let settings = app.state::<SettingsStore>().get();
+    let profile = SecureStorage::profile_index()?.active;
+    let client = Arc::new(OtsClient::from_config().await?);
+    let limiter = Arc::new(RateLimiter::new());
+    let permits = Arc::new(Semaphore::new(request.concurrency()));
+    let batch = bulk::batch_id();
+
+    let mut results: Vec<BulkRowResult> = (1..=rows.len())
+        .map(|row| BulkRowResult {
+            row,
+            error: Some("Cancelled".to_string()),
+            ..Default::default()
+        })
+        .collect();
+
+    let mut tasks = JoinSet::new();
+    for (index, row) in rows.into_iter().enumerate() {
+        let (secret, generated) =
+            row.into_request(settings.default_ttl, settings.require_passphrase);
+        let (client, limiter, permits) = (client.clone(), limiter.clone(), permits.clone());
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            let created = limiter.run(|| client.create_secret(&secret)).await;
+            (index, secret, generated, created)
+        });
+    }
+
+    let total = results.len();
+    let mut done = 0;
+    let mut stopped = false;
+    loop {
+        let joined = tokio::select! {
+            joined = tasks.join_next() => joined,
+            _ = operation.cancelled(), if !stopped => {
+                // Rows not started yet are skipped; requests in flight may
+                // already have created their secret, so they are awaited and
+                // recorded instead of being aborted
+                limiter.stop();
+                stopped = true;
+                continue;
+            }
+        };
+        let Some(joined) = joined else {
+            break;
+        };
+        let (index, secret, generated, created) = match joined {
+            Ok(done) => done,
+            Err(e) => {
+                log::warn!("Bulk creation task failed: {}", e);
+                continue;
+            }
+        };
+
+        let result = &mut results[index];
+        result.recipient = secret.recipient.clone();
+        match created {
+            Err(AppError::Cancelled) => continue,
+            Ok(response) => {
+                record_receipt(app, &profile, &response, &secret, Some(&batch));
+                result.link = Some(response.link);
+                result.metadata_key = Some(response.metadata_key);
+                result.generated_passphrase = generated;
+                result.error = None;
+            }
+            Err(e) => result.error = Some(e.to_string()),
+        }
+
+        done += 1;
+        operation.progress(done, total, Some(&*result));
+    }
+    tray::refresh_menu(app);
+
+    Ok(BulkReport::new(batch, results, stopped))
+}
+
+/// Export a bulk creation report as CSV, or as JSON for a `.json` path
+#[tauri::command]
+pub async fn export_bulk_report(report: BulkReport, path: PathBuf) -> Result<(), String> {
+    report.export(&path).map_err(|e| e.to_string())
 }
 
 /// Retrieve a secret by key (burns the secret)
+/// Split secrets are reassembled from all their chunks
+/// Shared files are held back and described in `file`, see `save_received_file`
+/// Answers to the user's open requests are decrypted with the request's key
 #[tauri::command]
 pub async fn retrieve_secret(
     request: RetrieveSecretRequest,
-) -> Result<RetrieveSecretResponse, String> {
-    retrieve_secret_internal(request)
+    files: State<'_, ReceivedFiles>,
+    history: State<'_, History>,
+) -> Result<RetrievedSecret, String> {
+    retrieve_secret_internal(request, &files, &history)
         .await
         .map_err(|e| e.to_string())
 }
 
 async fn retrieve_secret_internal(
     request: RetrieveSecretRequest,
-) -> AppResult<RetrieveSecretResponse> {
+    files: &ReceivedFiles,
+    history: &History,
+) -> AppResult<RetrievedSecret> {
     let client = OtsClient::from_config().await?;
-    client.retrieve_secret(&request).await
+    let mut response = client.retrieve_secret(&request).await?;
+
+    let mut chunks = None;
+    if let Some(manifest) = Manifest::detect(&response.secret) {
+        response.secret = retrieve_chunks(&client, &manifest, &request.passphrase).await?;
+        chunks = Some(manifest.chunks.len());
+    }
+
+    // The secret is burned by now, so on failure the ciphertext is returned
+    // instead of an error, leaving the user the only copy
+    let mut decrypted_with = encryption::encrypted_with(&response.secret);
+    let mut decrypt_error = None;
+    let mut answered_request = None;
+    if decrypted_with == Some(KeyKind::Age) {
+        let open = history.open_requests();
+        match incoming::decrypt_reply(&response.secret, &open) {
+            Ok(Some((id, plaintext))) => {
+                response.secret = plaintext;
+                decrypted_with = None;
+                if let Err(e) = close_request(history, &id, RequestStatus::Fulfilled) {
+                    log::warn!("Failed to close request {}: {}", id, e);
+                }
+                answered_request = Some(id);
+            }
+            // Not an answer to an open request, so try the user's own key
+            Ok(None) => {}
+            Err(e) => {
+                decrypted_with = None;
+                decrypt_error = Some(e.to_string());
+            }
+        }
+    }
+
+    if let Some(kind) = decrypted_with {
+        match encryption::decrypt(&response.secret, kind) {
+            Ok(plaintext) => response.secret = plaintext,
+            Err(e) => {
+                decrypted_with = None;
+                decrypt_error = Some(e.to_string());
+            }
+        }
+    }
+
+    let file = FileEnvelope::detect(&response.secret).map(|envelope| {
+        response.secret.clear();
+        files.insert(envelope)
+    });
+    let template = templates::parse(&response.secret);
+    let request = incoming::parse(&response.secret);
+
+    Ok(RetrievedSecret {
+        response,
+        file,
+        chunks,
+        decrypted_with,
+        decrypt_error,
+        template,
+        request,
+        answered_request,
+    })
+}
+
+/// Retrieve every chunk listed in a manifest and reassemble the payload
+///
+/// Chunks are burned as they are read, so a missing one means the payload
+/// is lost; the error says which. For the same reason this doesn't run as a
+/// cancellable operation: stopping halfway would lose the payload too.
+async fn retrieve_chunks(
+    client: &OtsClient,
+    manifest: &Manifest,
+    passphrase: &Option<String>,
+) -> AppResult<String> {
+    manifest.check()?;
+
+    let total = manifest.chunks.len();
+    let mut parts = Vec::with_capacity(total);
+    for chunk in &manifest.chunks {
+        let request = RetrieveSecretRequest {
+            key: chunk.key.clone(),
+            passphrase: passphrase.clone(),
+        };
+        let part = client.retrieve_secret(&request).await.map_err(|e| {
+            AppError::Api(format!(
+                "Chunk {} of {} is missing or was already burned, the payload cannot be reassembled: {}",
+                chunk.index + 1,
+                total,
+                e
+            ))
+        })?;
+        parts.push(part.secret);
+    }
+
+    manifest.reassemble(&parts)
+}
+
+/// Share a file as a secret, wrapped with its name and MIME type
+/// Files whose envelope exceeds the configured secret size limit are refused.
+/// Not a cancellable operation, as it makes a single request; see
+/// `share_files` for uploads that can be stopped
+#[tauri::command]
+pub async fn share_file(
+    app: AppHandle,
+    request: ShareFileRequest,
+    settings: State<'_, SettingsStore>,
+) -> Result<CreateSecretResponse, String> {
+    share_file_internal(&app, request, settings.get().local_max_secret_size)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn share_file_internal(
+    app: &AppHandle,
+    request: ShareFileRequest,
+    max_size: usize,
+) -> AppResult<CreateSecretResponse> {
+    let path = request.path.clone();
+    let envelope =
+        tokio::task::spawn_blocking(move || FileEnvelope::read(Path::new(&path), max_size))
+            .await
+            .map_err(|e| AppError::Storage(format!("File read task failed: {}", e)))??;
+
+    let secret = CreateSecretRequest {
+        secret: envelope.encode()?,
+        passphrase: request.passphrase,
+        ttl: request.ttl,
+        recipient: request.recipient,
+    };
+    share_secret(app, &secret).await
+}
+
+/// Describe files before sharing, for the confirmation step
+#[tauri::command]
+pub async fn preview_files(
+    paths: Vec<String>,
+    settings: State<'_, SettingsStore>,
+) -> Result<Vec<FilePreview>, String> {
+    let max_size = settings.get().local_max_secret_size;
+    tokio::task::spawn_blocking(move || {
+        paths
+            .iter()
+            .map(|path| FilePreview::read(Path::new(path), max_size))
+            .collect::<AppResult<Vec<_>>>()
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+/// Share several files as one zip archive or as one secret each
+/// Every file is read and size checked before the first secret is created;
+/// if a later secret fails, the earlier ones are still in the history.
+/// Runs as operation `request.id`, emitting progress per secret created.
+/// Once cancelled, the file being split is abandoned with its chunks burned
+/// and the files shared so far are returned
+#[tauri::command]
+pub async fn share_files(
+    app: AppHandle,
+    request: ShareFilesRequest,
+    settings: State<'_, SettingsStore>,
+) -> Result<Vec<SharedFile>, String> {
+    let id = request.id.clone();
+    let max_size = settings.get().local_max_secret_size;
+    let handle = app.clone();
+    operations::run(&app, id, move |operation| async move {
+        share_files_internal(&handle, request, max_size, &operation).await
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+async fn share_files_internal(
+    app: &AppHandle,
+    request: ShareFilesRequest,
+    max_size: usize,
+    operation: &Operation,
+) -> AppResult<Vec<SharedFile>> {
+    if request.paths.is_empty() {
+        return Err(AppError::InvalidInput("No files to share".to_string()));
+    }
+
+    // Split files may be as large as the chunks of one manifest allow
+    let limit = if request.chunked {
+        chunks::max_payload_size(max_size)
+    } else {
+        max_size
+    };
+    let paths: Vec<PathBuf> = request.paths.iter().map(PathBuf::from).collect();
+    let mode = request.mode;
+    let envelopes = tokio::task::spawn_blocking(move || match mode {
+        ShareMode::Archive if paths.len() > 1 => {
+            FileEnvelope::archive(&paths, limit).map(|envelope| vec![envelope])
+        }
+        _ => paths
+            .iter()
+            .map(|path| FileEnvelope::read(path, limit))
+            .collect(),
+    })
+    .await
+    .map_err(|e| AppError::Storage(format!("File read task failed: {}", e)))??;
+
+    let recipients = app.state::<Recipients>();
+    let total = envelopes.len();
+    let mut shared = Vec::with_capacity(total);
+    for envelope in envelopes {
+        if operation.is_cancelled() {
+            break;
+        }
+
+        let secret = CreateSecretRequest {
+            secret: envelope.encode()?,
+            passphrase: request.passphrase.clone(),
+            ttl: request.ttl,
+            recipient: request.recipient.clone(),
+        };
+        let secret = seal_for(&recipients, secret, request.encrypt_to.as_deref())?;
+        let response = match share_chunked(app, &secret, max_size, Some(operation)).await {
+            Ok(chunked) => chunked.response,
+            Err(AppError::Cancelled) => break,
+            Err(e) => return Err(e),
+        };
+        shared.push(SharedFile {
+            name: envelope.name,
+            response,
+        });
+        operation.progress(shared.len(), total, shared.last());
+    }
+
+    Ok(shared)
+}
+
+/// Write a file from a retrieved secret to the chosen path
+#[tauri::command]
+pub async fn save_received_file(
+    id: String,
+    path: String,
+    files: State<'_, ReceivedFiles>,
+) -> Result<(), String> {
+    save_received_file_internal(&id, &path, &files).map_err(|e| e.to_string())
+}
+
+fn save_received_file_internal(id: &str, path: &str, files: &ReceivedFiles) -> AppResult<()> {
+    let envelope = files.take(id)?;
+    let contents = envelope.contents()?;
+
+    // Keep the file around for another attempt if the location was not writable
+    if let Err(e) = store::write_private(Path::new(path), &contents) {
+        files.restore(id, envelope);
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+/// Drop a received file without saving it
+#[tauri::command]
+pub async fn discard_received_file(
+    id: String,
+    files: State<'_, ReceivedFiles>,
+) -> Result<(), String> {
+    files.take(&id).map(|_| ()).map_err(|e| e.to_string())
 }
 
 /// Get secret metadata without burning it
@@ -90,13 +978,682 @@ async fn get_metadata_internal(metadata_key: String) -> AppResult<String> {
     Ok(serde_json::to_string(&metadata)?)
 }
 
-/// Clear all stored configuration and credentials
+/// Clear stored configuration and credentials of the active profile
 #[tauri::command]
-pub async fn clear_api_config() -> Result<(), String> {
+pub async fn clear_api_config(app: AppHandle) -> Result<(), String> {
     SecureStorage::clear_config()
+        .map_err(|e| e.to_string())?;
+
+    csp::refresh(&app);
+    Ok(())
+}
+
+/// Report the configuration API commands will use and where each value came from
+/// The API key is always redacted
+#[tauri::command]
+pub async fn show_resolved_config() -> Result<ConfigReport, String> {
+    ResolvedConfig::load()
+        .map(|resolved| resolved.report())
         .map_err(|e| e.to_string())
 }
 
+/// Move a corrupted stored configuration aside so it can be entered again
+/// Uses the active profile unless `profile` names another one
+/// Returns false when the configuration was readable and left alone
+#[tauri::command]
+pub async fn recover_api_config(app: AppHandle, profile: Option<String>) -> Result<bool, String> {
+    let result = match profile {
+        Some(profile) => SecureStorage::recover_profile(&profile),
+        None => SecureStorage::profile_index()
+            .and_then(|index| SecureStorage::recover_profile(&index.active)),
+    };
+
+    let recovered = result.map_err(|e| e.to_string())?;
+    if recovered {
+        tauri::async_runtime::spawn(async move {
+            tray::refresh_status(&app).await;
+        });
+    }
+
+    Ok(recovered)
+}
+
+/// List profiles and the active one
+#[tauri::command]
+pub async fn list_profiles() -> Result<ProfileIndex, String> {
+    SecureStorage::profile_index().map_err(|e| e.to_string())
+}
+
+/// Make a profile the one used by all API commands
+#[tauri::command]
+pub async fn set_active_profile(app: AppHandle, profile: String) -> Result<(), String> {
+    tray::switch_profile(&app, &profile).map_err(|e| e.to_string())
+}
+
+/// Delete a profile and its credentials
+#[tauri::command]
+pub async fn delete_profile(app: AppHandle, profile: String) -> Result<(), String> {
+    SecureStorage::delete_profile(&profile).map_err(|e| e.to_string())?;
+    tray::refresh_menu(&app);
+    csp::refresh(&app);
+    Ok(())
+}
+
+/// List receipts of secrets created from this app, newest first
+#[tauri::command]
+pub async fn list_history(
+    limit: Option<usize>,
+    history: State<'_, History>,
+) -> Result<Vec<HistoryEntry>, String> {
+    Ok(history.recent(limit.unwrap_or(usize::MAX)))
+}
+
+/// Ask someone to send the user a secret
+/// The link carries the public half of a key made for this request alone;
+/// retrieving the answer decrypts it and closes the request
+#[tauri::command]
+pub async fn create_incoming_request(
+    app: AppHandle,
+    request: NewIncomingRequest,
+) -> Result<HistoryEntry, String> {
+    create_incoming_request_internal(&app, request)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn create_incoming_request_internal(
+    app: &AppHandle,
+    new: NewIncomingRequest,
+) -> AppResult<HistoryEntry> {
+    let incoming = IncomingRequest::open(new.note)?;
+    let request = CreateSecretRequest {
+        secret: incoming.body(),
+        passphrase: new.passphrase.filter(|p| !p.is_empty()),
+        ttl: new.ttl,
+        recipient: new.recipient,
+    };
+
+    let profile = SecureStorage::profile_index()?.active;
+    let created = async {
+        let client = OtsClient::from_config().await?;
+        client.create_secret(&request).await
+    }
+    .await;
+    let response = match created {
+        Ok(response) => response,
+        Err(e) => {
+            if let Err(e) = SecureStorage::delete_request_key(&incoming.id) {
+                log::warn!("Failed to delete key of {}: {}", incoming.id, e);
+            }
+            return Err(e);
+        }
+    };
+
+    let entry = HistoryEntry::new(&profile, &response, request.ttl, request.recipient.clone())
+        .with_request(incoming);
+    app.state::<History>().record(entry.clone())?;
+    tray::refresh_menu(app);
+
+    Ok(entry)
+}
+
+/// List the user's requests for secrets with their links, newest first
+#[tauri::command]
+pub async fn list_incoming_requests(
+    history: State<'_, History>,
+) -> Result<Vec<HistoryEntry>, String> {
+    Ok(history.requests())
+}
+
+/// Stop waiting for an answer and delete the request's key
+/// The request link stays valid until it expires or is burned from the
+/// history like any other secret; an answer sent later can't be decrypted
+#[tauri::command]
+pub async fn cancel_incoming_request(
+    id: String,
+    history: State<'_, History>,
+) -> Result<(), String> {
+    close_request(&history, &id, RequestStatus::Cancelled).map_err(|e| e.to_string())
+}
+
+fn close_request(history: &History, id: &str, status: RequestStatus) -> AppResult<()> {
+    history.close_request(id, status)?;
+    SecureStorage::delete_request_key(id)
+}
+
+/// Answer someone's request with a secret encrypted to its key
+#[tauri::command]
+pub async fn answer_incoming_request(
+    app: AppHandle,
+    answer: RequestAnswer,
+) -> Result<CreateSecretResponse, String> {
+    let request = answer.into_request().map_err(|e| e.to_string())?;
+    share_secret(&app, &request)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Burn or refresh many receipts from the history at once
+/// Runs as operation `request.id`, emitting progress after each receipt; when
+/// cancelled, requests in flight are aborted and the rest reported as cancelled
+#[tauri::command]
+pub async fn run_batch(app: AppHandle, request: BatchRequest) -> Result<BatchReport, String> {
+    let id = Some(request.id.clone());
+    let handle = app.clone();
+    operations::run(&app, id, move |operation| async move {
+        run_batch_internal(&handle, &request, &operation).await
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Stop a running operation, returning whether it was still running
+/// The operation stops at its next step and returns what it finished
+#[tauri::command]
+pub async fn cancel_operation(
+    id: String,
+    operations: State<'_, Operations>,
+) -> Result<bool, String> {
+    Ok(operations.cancel(&id))
+}
+
+async fn run_batch_internal(
+    app: &AppHandle,
+    request: &BatchRequest,
+    operation: &Operation,
+) -> AppResult<BatchReport> {
+    let history = app.state::<History>();
+    let entries = request.selection.entries(&history)?;
+    let clients = batch_clients(&entries).await;
+    let limiter = Arc::new(RateLimiter::new());
+    let permits = Arc::new(Semaphore::new(bulk::concurrency(request.concurrency)));
+
+    let mut items: Vec<BatchItem> = entries
+        .iter()
+        .map(|entry| BatchItem::cancelled(&entry.metadata_key))
+        .collect();
+
+    let mut tasks = JoinSet::new();
+    for (index, entry) in entries.into_iter().enumerate() {
+        let client = clients[&entry.profile].clone();
+        let (action, limiter, permits) = (request.action, limiter.clone(), permits.clone());
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            let key = entry.metadata_key;
+            let metadata = match client {
+                Ok(client) => {
+                    limiter
+                        .run(|| async {
+                            match action {
+                                BatchAction::Burn => client.burn_secret(&key).await,
+                                BatchAction::Refresh => client.get_metadata(&key).await,
+                            }
+                        })
+                        .await
+                }
+                Err(e) => Err(AppError::Configuration(e)),
+            };
+
+            (index, metadata)
+        });
+    }
+
+    let total = items.len();
+    let mut done = 0;
+    let mut stopped = false;
+    let mut states = Vec::new();
+    loop {
+        let joined = tokio::select! {
+            joined = tasks.join_next() => joined,
+            _ = operation.cancelled(), if !stopped => {
+                // Dropping the tasks drops their requests too
+                tasks.abort_all();
+                stopped = true;
+                continue;
+            }
+        };
+        let Some(joined) = joined else {
+            break;
+        };
+        let Ok((index, metadata)) = joined else {
+            continue;
+        };
+
+        let item = &mut items[index];
+        match metadata {
+            Ok(metadata) => {
+                let state = metadata.state.or_else(|| {
+                    (request.action == BatchAction::Burn).then(|| "burned".to_string())
+                });
+                if let Some(state) = &state {
+                    states.push((item.metadata_key.clone(), state.clone()));
+                }
+                item.state = state;
+                item.error = None;
+            }
+            Err(e) => item.error = Some(e.to_string()),
+        }
+
+        done += 1;
+        operation.progress(done, total, Some(&*item));
+    }
+
+    if let Err(e) = history.set_states(&states) {
+        log::warn!("Failed to record secret states: {}", e);
+    }
+
+    Ok(BatchReport::new(request, items, stopped))
+}
+
+/// One client per profile of the receipts, or why it couldn't be built
+async fn batch_clients(
+    entries: &[HistoryEntry],
+) -> HashMap<String, Result<Arc<OtsClient>, String>> {
+    let active = SecureStorage::profile_index().map(|index| index.active).ok();
+
+    let mut clients = HashMap::new();
+    for entry in entries {
+        if clients.contains_key(&entry.profile) {
+            continue;
+        }
+
+        // The active profile honors environment and config file overrides
+        let client = if active.as_deref() == Some(entry.profile.as_str()) {
+            OtsClient::from_config().await
+        } else {
+            match SecureStorage::load_profile(&entry.profile) {
+                Ok(Some(config)) => OtsClient::new(config).await,
+                Ok(None) => Err(AppError::Configuration(format!(
+                    "Profile {} no longer exists",
+                    entry.profile
+                ))),
+                Err(e) => Err(e),
+            }
+        };
+        clients.insert(
+            entry.profile.clone(),
+            client.map(Arc::new).map_err(|e| e.to_string()),
+        );
+    }
+
+    clients
+}
+
+/// Copy a secret link to the clipboard
+/// The link is cleared after `clear_after_secs` (0 disables clearing),
+/// defaulting to the clipboard timeout from settings
+#[tauri::command]
+pub async fn copy_link(
+    link: String,
+    clear_after_secs: Option<u64>,
+    clipboard: State<'_, ClipboardManager>,
+    settings: State<'_, SettingsStore>,
+) -> Result<(), String> {
+    let clear_after_secs = clear_after_secs.unwrap_or(settings.get().clipboard_clear_secs);
+    copy_link_internal(&link, clear_after_secs, &clipboard).map_err(|e| e.to_string())
+}
+
+fn copy_link_internal(
+    link: &str,
+    clear_after_secs: u64,
+    clipboard: &ClipboardManager,
+) -> AppResult<()> {
+    url::Url::parse(link)?;
+    clipboard.copy(link, clipboard::clear_delay(clear_after_secs))
+}
+
+/// Copy a revealed secret to the clipboard
+/// The secret is cleared after `clear_after_secs` (0 disables clearing),
+/// defaulting to the clipboard timeout from settings
+#[tauri::command]
+pub async fn copy_secret(
+    secret: String,
+    clear_after_secs: Option<u64>,
+    clipboard: State<'_, ClipboardManager>,
+    settings: State<'_, SettingsStore>,
+) -> Result<(), String> {
+    let clear_after_secs = clear_after_secs.unwrap_or(settings.get().clipboard_clear_secs);
+    clipboard
+        .copy(&secret, clipboard::clear_delay(clear_after_secs))
+        .map_err(|e| e.to_string())
+}
+
+/// Clear the clipboard immediately
+#[tauri::command]
+pub async fn clear_clipboard(clipboard: State<'_, ClipboardManager>) -> Result<(), String> {
+    clipboard.clear().map_err(|e| e.to_string())
+}
+
+/// Render a share link as a QR code (SVG and PNG data URIs)
+#[tauri::command]
+pub async fn render_qr(request: QrRequest) -> Result<QrImage, String> {
+    qr::render(&request).map_err(|e| e.to_string())
+}
+
+/// List the public keys of known recipients
+#[tauri::command]
+pub async fn list_recipient_keys(
+    recipients: State<'_, Recipients>,
+) -> Result<Vec<RecipientKey>, String> {
+    Ok(recipients.list())
+}
+
+/// Add or replace a recipient's age or OpenPGP public key
+#[tauri::command]
+pub async fn add_recipient_key(
+    name: String,
+    public_key: String,
+    recipients: State<'_, Recipients>,
+) -> Result<RecipientKey, String> {
+    recipients
+        .add(&name, &public_key)
+        .map_err(|e| e.to_string())
+}
+
+/// Forget a recipient's public key
+#[tauri::command]
+pub async fn remove_recipient_key(
+    name: String,
+    recipients: State<'_, Recipients>,
+) -> Result<(), String> {
+    recipients.remove(&name).map_err(|e| e.to_string())
+}
+
+/// List the secret templates
+#[tauri::command]
+pub async fn list_templates(
+    templates: State<'_, Templates>,
+) -> Result<Vec<SecretTemplate>, String> {
+    Ok(templates.list())
+}
+
+/// Add a secret template or replace the one with the same ID
+#[tauri::command]
+pub async fn save_template(
+    template: SecretTemplate,
+    templates: State<'_, Templates>,
+) -> Result<(), String> {
+    templates.save(template).map_err(|e| e.to_string())
+}
+
+/// Delete a secret template
+#[tauri::command]
+pub async fn delete_template(id: String, templates: State<'_, Templates>) -> Result<(), String> {
+    templates.delete(&id).map_err(|e| e.to_string())
+}
+
+/// Show which of the user's own private keys are set up
+#[tauri::command]
+pub async fn get_own_keys() -> Result<OwnKeys, String> {
+    encryption::own_keys().map_err(|e| e.to_string())
+}
+
+/// Create the user's age key, returning the recipient to hand out
+#[tauri::command]
+pub async fn generate_age_key() -> Result<String, String> {
+    encryption::generate_age_key().map_err(|e| e.to_string())
+}
+
+/// Import the user's OpenPGP secret key into the keychain
+#[tauri::command]
+pub async fn import_openpgp_key(
+    secret_key: String,
+    passphrase: Option<String>,
+) -> Result<(), String> {
+    encryption::import_openpgp_key(&secret_key, passphrase).map_err(|e| e.to_string())
+}
+
+/// Delete one of the user's private keys from the keychain
+/// Secrets already encrypted to it can no longer be read
+#[tauri::command]
+pub async fn delete_own_key(kind: KeyKind) -> Result<(), String> {
+    SecureStorage::delete_private_key(kind).map_err(|e| e.to_string())
+}
+
+/// Take the deep link actions queued since the last call
+/// The frontend calls this on startup and whenever a `deep-link` event fires
+#[tauri::command]
+pub async fn take_deep_links(queue: State<'_, DeepLinkQueue>) -> Result<Vec<DeepLinkAction>, String> {
+    Ok(queue.take())
+}
+
+/// Take the file paths passed on the command line since the last call
+/// The frontend calls this on startup and whenever an `open-files` event fires
+#[tauri::command]
+pub async fn take_open_files(pending: State<'_, PendingFiles>) -> Result<Vec<String>, String> {
+    Ok(pending
+        .take()
+        .into_iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect())
+}
+
+/// Get the user preferences
+#[tauri::command]
+pub async fn get_settings(settings: State<'_, SettingsStore>) -> Result<AppSettings, String> {
+    Ok(settings.get())
+}
+
+/// Validate and save the user preferences
+/// Hotkey changes are registered first; on a conflict nothing is saved
+#[tauri::command]
+pub async fn update_settings(
+    app: AppHandle,
+    settings: AppSettings,
+    store: State<'_, SettingsStore>,
+) -> Result<AppSettings, String> {
+    update_settings_internal(&app, settings, &store).map_err(|e| e.to_string())
+}
+
+fn update_settings_internal(
+    app: &AppHandle,
+    settings: AppSettings,
+    store: &SettingsStore,
+) -> AppResult<AppSettings> {
+    settings.validate()?;
+
+    let previous = store.get();
+    if settings.hotkeys != previous.hotkeys {
+        register_hotkeys(app, &settings.hotkeys, &previous.hotkeys)?;
+    }
+
+    let updated = store.update(|s| *s = settings)?;
+
+    if updated.history_retention_days != previous.history_retention_days {
+        if let Err(e) = app.state::<History>().prune(updated.history_retention_days) {
+            log::warn!("Failed to prune history: {}", e);
+        }
+    }
+    tray::refresh_menu(app);
+
+    Ok(updated)
+}
+
+/// Get the configured global hotkeys
+#[tauri::command]
+pub async fn get_hotkeys(settings: State<'_, SettingsStore>) -> Result<HotkeySettings, String> {
+    Ok(settings.get().hotkeys)
+}
+
+/// Register new global hotkeys and save them
+/// On a conflict the previous hotkeys are restored and the error is returned
+#[tauri::command]
+pub async fn update_hotkeys(
+    app: AppHandle,
+    hotkeys: HotkeySettings,
+    settings: State<'_, SettingsStore>,
+) -> Result<HotkeySettings, String> {
+    update_hotkeys_internal(&app, hotkeys, &settings).map_err(|e| e.to_string())
+}
+
+fn update_hotkeys_internal(
+    app: &AppHandle,
+    hotkeys: HotkeySettings,
+    settings: &SettingsStore,
+) -> AppResult<HotkeySettings> {
+    register_hotkeys(app, &hotkeys, &settings.get().hotkeys)?;
+    let updated = settings.update(|s| s.hotkeys = hotkeys)?;
+    Ok(updated.hotkeys)
+}
+
+/// Register hotkeys, restoring the previous ones if that fails
+fn register_hotkeys(
+    app: &AppHandle,
+    hotkeys: &HotkeySettings,
+    previous: &HotkeySettings,
+) -> AppResult<()> {
+    if let Err(e) = hotkeys::apply(app, hotkeys) {
+        if let Err(restore) = hotkeys::apply(app, previous) {
+            log::warn!("Failed to restore previous hotkeys: {}", restore);
+        }
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+/// Write profiles, settings and history to a passphrase-encrypted file
+/// API keys, proxy passwords and client certificate secrets are only included
+/// when `include_api_keys` is set
+#[tauri::command]
+pub async fn export_bundle(
+    request: ExportRequest,
+    settings: State<'_, SettingsStore>,
+    history: State<'_, History>,
+) -> Result<ExportReport, String> {
+    export_bundle_internal(request, &settings, &history)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn export_bundle_internal(
+    request: ExportRequest,
+    settings: &SettingsStore,
+    history: &History,
+) -> AppResult<ExportReport> {
+    let index = SecureStorage::profile_index()?;
+
+    let mut profiles = Vec::new();
+    for name in &index.names {
+        let Some(mut config) = SecureStorage::load_profile(name)? else {
+            continue;
+        };
+        if !request.include_api_keys {
+            config = config.without_secrets();
+        }
+        profiles.push(BundleProfile {
+            name: name.clone(),
+            config,
+        });
+    }
+
+    let bundle = Bundle::new(
+        index.active,
+        profiles,
+        settings.get(),
+        history.recent(usize::MAX),
+    );
+    let report = ExportReport {
+        path: request.path.clone(),
+        profiles: bundle.profiles.iter().map(|p| p.name.clone()).collect(),
+        history_entries: bundle.history.len(),
+        includes_api_keys: bundle.has_api_keys(),
+    };
+
+    // Passphrase key derivation is deliberately slow
+    let sealed = tokio::task::spawn_blocking(move || bundle::seal(&bundle, &request.passphrase))
+        .await
+        .map_err(|e| AppError::Storage(format!("Export task failed: {}", e)))??;
+    store::write_private(Path::new(&report.path), &sealed)?;
+
+    Ok(report)
+}
+
+/// Read a bundle and merge it into this install
+/// Existing profiles are handled according to `on_conflict`
+#[tauri::command]
+pub async fn import_bundle(
+    app: AppHandle,
+    request: ImportRequest,
+    settings: State<'_, SettingsStore>,
+    history: State<'_, History>,
+) -> Result<ImportReport, String> {
+    import_bundle_internal(&app, request, &settings, &history)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn import_bundle_internal(
+    app: &AppHandle,
+    request: ImportRequest,
+    settings: &SettingsStore,
+    history: &History,
+) -> AppResult<ImportReport> {
+    let data = std::fs::read(&request.path)?;
+    let passphrase = request.passphrase;
+    let bundle = tokio::task::spawn_blocking(move || bundle::open(&data, &passphrase))
+        .await
+        .map_err(|e| AppError::Storage(format!("Import task failed: {}", e)))??;
+
+    let mut report = ImportReport::default();
+
+    // Settings go first so a rejected hotkey aborts before anything is written
+    if request.import_settings {
+        update_settings_internal(app, bundle.settings, settings)?;
+        report.settings_imported = true;
+    }
+
+    let mut existing = SecureStorage::profile_index()?.names;
+    for profile in bundle.profiles {
+        let exists = existing.contains(&profile.name);
+        let name = match (exists, request.on_conflict) {
+            (false, _) => {
+                report.imported.push(profile.name.clone());
+                profile.name
+            }
+            (true, ConflictStrategy::Skip) => {
+                report.skipped.push(profile.name);
+                continue;
+            }
+            (true, ConflictStrategy::Overwrite) => {
+                // Stored credentials the bundle doesn't replace must not
+                // follow the profile to another host
+                let same_host = SecureStorage::load_profile(&profile.name)
+                    .ok()
+                    .flatten()
+                    .is_some_and(|local| local.base_url == profile.config.base_url);
+                if !same_host {
+                    SecureStorage::clear_credentials(&profile.name)?;
+                }
+                report.overwritten.push(profile.name.clone());
+                profile.name
+            }
+            (true, ConflictStrategy::Rename) => {
+                let to = bundle::free_name(&profile.name, &existing);
+                report.renamed.push(RenamedProfile {
+                    from: profile.name,
+                    to: to.clone(),
+                });
+                to
+            }
+        };
+
+        SecureStorage::save_profile(&name, &profile.config)?;
+        existing.push(name);
+    }
+
+    let imported_history = bundle::imported_history(bundle.history, &report);
+    report.history_entries = history.merge(imported_history)?;
+    if let Err(e) = history.prune(settings.get().history_retention_days) {
+        log::warn!("Failed to prune history: {}", e);
+    }
+
+    tray::refresh_menu(app);
+    csp::refresh(app);
+
+    Ok(report)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,188 @@
+use crate::resolve;
+use crate::storage::{ApiConfig, SecureStorage};
+use std::sync::{Arc, RwLock};
+use tauri::{AppHandle, Manager};
+
+/// Response header carrying the policy of the app's pages
+pub const CSP_HEADER: &str = "Content-Security-Policy";
+
+/// Origins of the configured instances, added to `connect-src`
+///
+/// `tauri.conf.json` only allows onetimesecret.com. The main window's
+/// responses get these origins added so the frontend can reach self-hosted
+/// instances too; the list is shared with the response handler.
+#[derive(Debug, Clone, Default)]
+pub struct ConnectSources(Arc<RwLock<Vec<String>>>);
+
+impl ConnectSources {
+    pub fn get(&self) -> Vec<String> {
+        self.0.read().unwrap().clone()
+    }
+
+    /// Replace the origins, returning whether they changed
+    pub fn set(&self, sources: Vec<String>) -> bool {
+        let mut current = self.0.write().unwrap();
+        if *current == sources {
+            return false;
+        }
+
+        *current = sources;
+        true
+    }
+
+    /// Add the origins to the `connect-src` directive of a policy
+    pub fn apply(&self, policy: &str) -> String {
+        with_connect_sources(policy, &self.get())
+    }
+}
+
+/// Origins of all saved profiles and of any environment or file override
+pub fn configured_sources() -> Vec<String> {
+    let mut configs = Vec::new();
+
+    match SecureStorage::profile_index() {
+        Ok(index) => {
+            for name in &index.names {
+                match SecureStorage::load_profile(name) {
+                    Ok(Some(config)) => configs.push(config),
+                    Ok(None) => {}
+                    Err(e) => log::warn!("Skipping profile '{}' in CSP: {}", name, e),
+                }
+            }
+        }
+        Err(e) => log::warn!("Failed to read profiles for CSP: {}", e),
+    }
+
+    match resolve::api_config() {
+        Ok(Some(config)) => configs.push(config),
+        Ok(None) => {}
+        Err(e) => log::warn!("Skipping resolved config in CSP: {}", e),
+    }
+
+    origins(&configs)
+}
+
+/// Recompute the origins for the next page load
+///
+/// A loaded page keeps the policy it was served with. The main window is
+/// not reloaded for it, which would throw away whatever the user is typing;
+/// API requests go through the backend, so nothing waits on the new policy.
+pub fn refresh(app: &AppHandle) {
+    let sources = app.state::<ConnectSources>();
+    if sources.set(configured_sources()) {
+        log::info!(
+            "Allowed API origins changed, applied from the next page load: {}",
+            sources.get().join(" ")
+        );
+    }
+}
+
+/// Sorted, deduplicated origins of the profiles' base URLs
+fn origins(configs: &[ApiConfig]) -> Vec<String> {
+    let mut origins: Vec<String> = configs
+        .iter()
+        .filter_map(|config| url::Url::parse(&config.base_url).ok())
+        .map(|url| url.origin())
+        .filter(|origin| origin.is_tuple())
+        .map(|origin| origin.ascii_serialization())
+        .collect();
+
+    origins.sort();
+    origins.dedup();
+    origins
+}
+
+/// Add sources to `connect-src`, keeping every other directive as it is
+///
+/// Tauri adds its IPC origins and script hashes to the configured policy,
+/// so the served header is extended rather than replaced.
+fn with_connect_sources(policy: &str, sources: &[String]) -> String {
+    let mut found = false;
+
+    let mut directives: Vec<String> = policy
+        .split(';')
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+        .map(|directive| {
+            let mut parts: Vec<&str> = directive.split_whitespace().collect();
+            if !parts[0].eq_ignore_ascii_case("connect-src") {
+                return directive.to_string();
+            }
+
+            found = true;
+            for source in sources {
+                if !parts.contains(&source.as_str()) {
+                    parts.push(source);
+                }
+            }
+            parts.join(" ")
+        })
+        .collect();
+
+    if !found {
+        let mut directive = vec!["connect-src", "'self'"];
+        directive.extend(sources.iter().map(String::as_str));
+        directives.push(directive.join(" "));
+    }
+
+    directives.join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(base_url: &str) -> ApiConfig {
+        ApiConfig {
+            base_url: base_url.to_string(),
+            username: "test@example.com".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_origins() {
+        let configs = vec![
+            config("https://ots.corp.example.com:8443/"),
+            config("https://onetimesecret.com"),
+            config("https://ots.corp.example.com:8443/api"),
+            config("http://localhost:3000"),
+            config("not a url"),
+        ];
+
+        assert_eq!(
+            origins(&configs),
+            vec![
+                "http://localhost:3000",
+                "https://onetimesecret.com",
+                "https://ots.corp.example.com:8443",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_with_connect_sources() {
+        let policy = "default-src 'self'; connect-src ipc: http://ipc.localhost https://onetimesecret.com; img-src 'self' data:";
+        let sources = vec![
+            "https://onetimesecret.com".to_string(),
+            "https://ots.corp.example.com".to_string(),
+        ];
+
+        assert_eq!(
+            with_connect_sources(policy, &sources),
+            "default-src 'self'; connect-src ipc: http://ipc.localhost https://onetimesecret.com https://ots.corp.example.com; img-src 'self' data:"
+        );
+        assert_eq!(
+            with_connect_sources("default-src 'self';", &sources[1..]),
+            "default-src 'self'; connect-src 'self' https://ots.corp.example.com"
+        );
+    }
+
+    #[test]
+    fn test_set_reports_changes() {
+        let sources = ConnectSources::default();
+        assert!(sources.set(vec!["https://onetimesecret.com".to_string()]));
+        assert!(!sources.set(vec!["https://onetimesecret.com".to_string()]));
+        assert_eq!(sources.get().len(), 1);
+    }
+}
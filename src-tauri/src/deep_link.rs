@@ -0,0 +1,328 @@
+use crate::error::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use url::Url;
+
+/// URL scheme registered with the operating system
+pub const SCHEME: &str = "onetimesecret";
+
+/// Event emitted to the frontend when new deep link actions are queued
+pub const DEEP_LINK_EVENT: &str = "deep-link";
+
+/// Longest key accepted from a deep link
+const MAX_KEY_LENGTH: usize = 128;
+
+/// Action requested by an `onetimesecret://` link
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum DeepLinkAction {
+    /// `onetimesecret://secret/<key>` - retrieve (and burn) a secret
+    Retrieve { key: String },
+    /// `onetimesecret://private/<metadata_key>` - show a secret's metadata
+    Metadata { metadata_key: String },
+    /// `onetimesecret://share?secret=..&ttl=..&recipient=..` - prefill the share form
+    Share {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        secret: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        ttl: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        recipient: Option<String>,
+    },
+}
+
+/// Deep link actions waiting for the frontend to pick them up
+///
+/// Links can arrive before the webview is listening (e.g. when the link
+/// launched the app), so actions are queued and the event only tells the
+/// frontend to drain the queue.
+#[derive(Default)]
+pub struct DeepLinkQueue {
+    pending: Mutex<Vec<DeepLinkAction>>,
+}
+
+impl DeepLinkQueue {
+    pub fn push(&self, action: DeepLinkAction) {
+        self.pending.lock().unwrap().push(action);
+    }
+
+    pub fn take(&self) -> Vec<DeepLinkAction> {
+        std::mem::take(&mut *self.pending.lock().unwrap())
+    }
+}
+
+/// Parse and validate an `onetimesecret://` link
+///
+/// Both `onetimesecret://secret/<key>` and `onetimesecret:secret/<key>`
+/// forms are accepted.
+pub fn parse(link: &str) -> AppResult<DeepLinkAction> {
+    let url = Url::parse(link.trim())?;
+
+    if url.scheme() != SCHEME {
+        return Err(AppError::InvalidInput(format!(
+            "Unsupported link scheme: {}",
+            url.scheme()
+        )));
+    }
+
+    let mut segments: Vec<&str> = Vec::new();
+    if let Some(host) = url.host_str() {
+        segments.push(host);
+    }
+    segments.extend(url.path().split('/').filter(|s| !s.is_empty()));
+
+    match segments.as_slice() {
+        ["secret", key] => {
+            reject_query(&url)?;
+            Ok(DeepLinkAction::Retrieve {
+                key: validate_key(key)?,
+            })
+        }
+        ["private", metadata_key] => {
+            reject_query(&url)?;
+            Ok(DeepLinkAction::Metadata {
+                metadata_key: validate_key(metadata_key)?,
+            })
+        }
+        ["share"] => parse_share(&url),
+        _ => Err(AppError::InvalidInput(format!(
+            "Unrecognized link: {}",
+            redact(&url)
+        ))),
+    }
+}
+
+/// Parse a web link to a secret on the configured instance
+///
+/// `https://<instance>/secret/<key>` and `https://<instance>/private/<key>`
+/// are accepted only when their origin matches `base_url`, since the
+/// secret is retrieved through the configured client.
+pub fn parse_web_link(link: &str, base_url: &str) -> AppResult<DeepLinkAction> {
+    let url = Url::parse(link.trim())?;
+    let base = Url::parse(base_url)?;
+
+    if url.origin() != base.origin() {
+        return Err(AppError::InvalidInput(
+            "Link does not belong to the configured instance".to_string(),
+        ));
+    }
+
+    let segments: Vec<&str> = url.path().split('/').filter(|s| !s.is_empty()).collect();
+    match segments.as_slice() {
+        ["secret", key] => Ok(DeepLinkAction::Retrieve {
+            key: validate_key(key)?,
+        }),
+        ["private", metadata_key] => Ok(DeepLinkAction::Metadata {
+            metadata_key: validate_key(metadata_key)?,
+        }),
+        _ => Err(AppError::InvalidInput(format!(
+            "Unrecognized link: {}",
+            redact(&url)
+        ))),
+    }
+}
+
+fn parse_share(url: &Url) -> AppResult<DeepLinkAction> {
+    let mut secret = None;
+    let mut ttl = None;
+    let mut recipient = None;
+
+    for (name, value) in url.query_pairs() {
+        match name.as_ref() {
+            "secret" if !value.is_empty() => secret = Some(value.into_owned()),
+            "ttl" => {
+                let seconds: u32 = value
+                    .parse()
+                    .map_err(|_| AppError::InvalidInput(format!("Invalid TTL: {}", value)))?;
+                if seconds == 0 || seconds > 604800 {
+                    return Err(AppError::InvalidInput(
+                        "TTL must be between 1 second and 7 days (604800 seconds)".to_string(),
+                    ));
+                }
+                ttl = Some(seconds);
+            }
+            "recipient" => {
+                if !value.contains('@') {
+                    return Err(AppError::InvalidInput(
+                        "Recipient must be a valid email address".to_string(),
+                    ));
+                }
+                recipient = Some(value.into_owned());
+            }
+            "secret" => {}
+            other => {
+                return Err(AppError::InvalidInput(format!(
+                    "Unsupported share parameter: {}",
+                    other
+                )))
+            }
+        }
+    }
+
+    Ok(DeepLinkAction::Share {
+        secret,
+        ttl,
+        recipient,
+    })
+}
+
+/// Secret and metadata keys are short alphanumeric identifiers
+fn validate_key(key: &str) -> AppResult<String> {
+    if key.is_empty()
+        || key.len() > MAX_KEY_LENGTH
+        || !key.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        return Err(AppError::InvalidInput("Invalid key in link".to_string()));
+    }
+
+    Ok(key.to_string())
+}
+
+fn reject_query(url: &Url) -> AppResult<()> {
+    if url.query().is_some_and(|q| !q.is_empty()) {
+        return Err(AppError::InvalidInput(
+            "Unexpected parameters in link".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Link without its query, which may hold a prefilled secret
+fn redact(url: &Url) -> String {
+    let mut url = url.clone();
+    url.set_query(None);
+    url.to_string()
+}
+
+/// Queue the actions for a set of incoming links and notify the frontend
+/// Links that fail validation are logged and dropped
+pub fn dispatch<I, S>(app: &AppHandle, links: I)
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let actions = links
+        .into_iter()
+        .filter_map(|link| match parse(link.as_ref()) {
+            Ok(action) => Some(action),
+            Err(e) => {
+                log::warn!("Ignoring deep link: {}", e);
+                None
+            }
+        })
+        .collect();
+
+    enqueue(app, actions);
+}
+
+/// Queue already parsed actions and notify the frontend
+pub fn enqueue(app: &AppHandle, actions: Vec<DeepLinkAction>) {
+    if actions.is_empty() {
+        return;
+    }
+
+    let queue = app.state::<DeepLinkQueue>();
+    for action in actions {
+        queue.push(action);
+    }
+
+    if let Err(e) = app.emit(DEEP_LINK_EVENT, ()) {
+        log::warn!("Failed to notify frontend of deep link: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_retrieve() {
+        assert_eq!(
+            parse("onetimesecret://secret/abc123").unwrap(),
+            DeepLinkAction::Retrieve {
+                key: "abc123".to_string()
+            }
+        );
+        assert_eq!(
+            parse("onetimesecret:secret/abc123").unwrap(),
+            DeepLinkAction::Retrieve {
+                key: "abc123".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_metadata() {
+        assert_eq!(
+            parse("onetimesecret://private/meta456").unwrap(),
+            DeepLinkAction::Metadata {
+                metadata_key: "meta456".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_share() {
+        assert_eq!(
+            parse("onetimesecret://share?secret=hunter2&ttl=3600&recipient=a%40example.com")
+                .unwrap(),
+            DeepLinkAction::Share {
+                secret: Some("hunter2".to_string()),
+                ttl: Some(3600),
+                recipient: Some("a@example.com".to_string()),
+            }
+        );
+        assert_eq!(
+            parse("onetimesecret://share").unwrap(),
+            DeepLinkAction::Share {
+                secret: None,
+                ttl: None,
+                recipient: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_links() {
+        assert!(parse("https://onetimesecret.com/secret/abc").is_err());
+        assert!(parse("onetimesecret://secret/").is_err());
+        assert!(parse("onetimesecret://secret/abc/def").is_err());
+        assert!(parse("onetimesecret://secret/ab%2F..").is_err());
+        assert!(parse("onetimesecret://secret/abc?passphrase=x").is_err());
+        assert!(parse("onetimesecret://share?ttl=0").is_err());
+        assert!(parse("onetimesecret://share?ttl=999999999").is_err());
+        assert!(parse("onetimesecret://share?recipient=nobody").is_err());
+        assert!(parse("onetimesecret://share?passphrase=x").is_err());
+        assert!(parse("onetimesecret://burn/abc").is_err());
+    }
+
+    #[test]
+    fn test_parse_web_link() {
+        let base = "https://onetimesecret.com";
+        assert_eq!(
+            parse_web_link("https://onetimesecret.com/secret/abc123", base).unwrap(),
+            DeepLinkAction::Retrieve {
+                key: "abc123".to_string()
+            }
+        );
+        assert_eq!(
+            parse_web_link("https://onetimesecret.com/private/meta456", base).unwrap(),
+            DeepLinkAction::Metadata {
+                metadata_key: "meta456".to_string()
+            }
+        );
+        assert!(parse_web_link("https://evil.example/secret/abc123", base).is_err());
+        assert!(parse_web_link("http://onetimesecret.com/secret/abc123", base).is_err());
+        assert!(parse_web_link("https://onetimesecret.com/about", base).is_err());
+    }
+
+    #[test]
+    fn test_queue_drains() {
+        let queue = DeepLinkQueue::default();
+        queue.push(parse("onetimesecret://secret/abc").unwrap());
+        assert_eq!(queue.take().len(), 1);
+        assert!(queue.take().is_empty());
+    }
+}
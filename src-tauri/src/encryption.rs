@@ -0,0 +1,257 @@
+use crate::error::{AppError, AppResult};
+use crate::recipients::{KeyKind, RecipientKey};
+use crate::storage::SecureStorage;
+use age::secrecy::ExposeSecret;
+use pgp::crypto::sym::SymmetricKeyAlgorithm;
+use pgp::types::PublicKeyTrait;
+use pgp::{ArmorOptions, Deserializable, Message, SignedPublicKey, SignedSecretKey};
+use serde::{Deserialize, Serialize};
+
+/// First line of an armored age message
+const AGE_ARMOR_BEGIN: &str = "-----BEGIN AGE ENCRYPTED FILE-----";
+
+/// First line of an armored OpenPGP message
+const OPENPGP_ARMOR_BEGIN: &str = "-----BEGIN PGP MESSAGE-----";
+
+/// The user's own keys for secrets encrypted to them
+#[derive(Debug, Default, Serialize)]
+pub struct OwnKeys {
+    /// The `age1…` recipient others encrypt to, if an age key is set up
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub age: Option<String>,
+    /// Whether an OpenPGP private key was imported
+    pub openpgp: bool,
+}
+
+/// OpenPGP private key as kept in the keychain
+#[derive(Serialize, Deserialize)]
+struct OpenpgpSecret {
+    /// Armored secret key
+    key: String,
+    #[serde(default)]
+    passphrase: Option<String>,
+}
+
+/// Check that a public key can be encrypted to, returning its kind
+pub fn check_public_key(public_key: &str) -> AppResult<KeyKind> {
+    if public_key.starts_with("age1") {
+        age_recipient(public_key)?;
+        return Ok(KeyKind::Age);
+    }
+
+    if public_key.contains("-----BEGIN PGP PUBLIC KEY BLOCK-----") {
+        let key = openpgp_public_key(public_key)?;
+        openpgp_encryption_subkey(&key)?;
+        return Ok(KeyKind::Openpgp);
+    }
+
+    Err(AppError::InvalidInput(
+        "Expected an age recipient (age1…) or an armored OpenPGP public key".to_string(),
+    ))
+}
+
+/// Encrypt a secret to a recipient, producing armored text
+pub fn encrypt(recipient: &RecipientKey, plaintext: &str) -> AppResult<String> {
+    match recipient.kind {
+        KeyKind::Age => {
+            let key = age_recipient(&recipient.public_key)?;
+            age::encrypt_and_armor(&key, plaintext.as_bytes()).map_err(encryption_error)
+        }
+        KeyKind::Openpgp => encrypt_openpgp(&recipient.public_key, plaintext.as_bytes()),
+    }
+}
+
+/// The kind of key a retrieved secret is encrypted to, if it is
+pub fn encrypted_with(secret: &str) -> Option<KeyKind> {
+    let secret = secret.trim_start();
+    if secret.starts_with(AGE_ARMOR_BEGIN) {
+        Some(KeyKind::Age)
+    } else if secret.starts_with(OPENPGP_ARMOR_BEGIN) {
+        Some(KeyKind::Openpgp)
+    } else {
+        None
+    }
+}
+
+/// Decrypt a secret with the user's private key from the keychain
+pub fn decrypt(secret: &str, kind: KeyKind) -> AppResult<String> {
+    let private_key = SecureStorage::load_private_key(kind)?.ok_or_else(|| {
+        AppError::Configuration(format!(
+            "This secret is encrypted to an {} key, but none is set up in this app",
+            kind.label()
+        ))
+    })?;
+
+    let plaintext = match kind {
+        KeyKind::Age => {
+            let identity = private_key
+                .parse::<age::x25519::Identity>()
+                .map_err(|e| AppError::CorruptedConfig(format!("Stored age key: {}", e)))?;
+            age::decrypt(&identity, secret.as_bytes()).map_err(|e| match e {
+                age::DecryptError::NoMatchingKeys => AppError::InvalidInput(
+                    "This secret was encrypted to someone else's age key".to_string(),
+                ),
+                e => AppError::InvalidInput(format!("Failed to decrypt secret: {}", e)),
+            })?
+        }
+        KeyKind::Openpgp => {
+            let stored: OpenpgpSecret = serde_json::from_str(&private_key)
+                .map_err(|e| AppError::CorruptedConfig(format!("Stored OpenPGP key: {}", e)))?;
+            decrypt_openpgp(&stored, secret)?
+        }
+    };
+
+    String::from_utf8(plaintext)
+        .map_err(|_| AppError::InvalidInput("Decrypted secret is not text".to_string()))
+}
+
+/// Describe the private keys set up in the keychain
+pub fn own_keys() -> AppResult<OwnKeys> {
+    let age = match SecureStorage::load_private_key(KeyKind::Age)? {
+        Some(identity) => Some(
+            identity
+                .parse::<age::x25519::Identity>()
+                .map_err(|e| AppError::CorruptedConfig(format!("Stored age key: {}", e)))?
+                .to_public()
+                .to_string(),
+        ),
+        None => None,
+    };
+
+    Ok(OwnKeys {
+        age,
+        openpgp: SecureStorage::load_private_key(KeyKind::Openpgp)?.is_some(),
+    })
+}
+
+/// Create an age key in the keychain, returning the recipient to share
+///
+/// An existing key is never replaced, as secrets encrypted to it could no
+/// longer be read.
+pub fn generate_age_key() -> AppResult<String> {
+    if SecureStorage::load_private_key(KeyKind::Age)?.is_some() {
+        return Err(AppError::InvalidInput(
+            "An age key is already set up, delete it first".to_string(),
+        ));
+    }
+
+    let identity = age::x25519::Identity::generate();
+    SecureStorage::save_private_key(KeyKind::Age, identity.to_string().expose_secret())?;
+    Ok(identity.to_public().to_string())
+}
+
+/// Store an armored OpenPGP secret key in the keychain after checking it
+pub fn import_openpgp_key(secret_key: &str, passphrase: Option<String>) -> AppResult<()> {
+    let (key, _) = SignedSecretKey::from_string(secret_key.trim()).map_err(invalid_key)?;
+    key.verify().map_err(invalid_key)?;
+
+    let stored = OpenpgpSecret {
+        key: secret_key.trim().to_string(),
+        passphrase: passphrase.filter(|p| !p.is_empty()),
+    };
+    SecureStorage::save_private_key(KeyKind::Openpgp, &serde_json::to_string(&stored)?)
+}
+
+fn age_recipient(public_key: &str) -> AppResult<age::x25519::Recipient> {
+    public_key
+        .trim()
+        .parse()
+        .map_err(|e| AppError::InvalidInput(format!("Invalid age recipient: {}", e)))
+}
+
+fn openpgp_public_key(public_key: &str) -> AppResult<SignedPublicKey> {
+    let (key, _) = SignedPublicKey::from_string(public_key).map_err(invalid_key)?;
+    key.verify().map_err(invalid_key)?;
+    Ok(key)
+}
+
+/// The subkey to encrypt to; primary keys of modern OpenPGP keys only sign
+fn openpgp_encryption_subkey(key: &SignedPublicKey) -> AppResult<&pgp::SignedPublicSubKey> {
+    key.public_subkeys
+        .iter()
+        .find(|subkey| subkey.is_encryption_key())
+        .ok_or_else(|| AppError::InvalidInput("OpenPGP key has no encryption subkey".to_string()))
+}
+
+fn encrypt_openpgp(public_key: &str, plaintext: &[u8]) -> AppResult<String> {
+    let key = openpgp_public_key(public_key)?;
+    let subkey = openpgp_encryption_subkey(&key)?;
+
+    let mut rng = rand::thread_rng();
+    Message::new_literal_bytes("", plaintext)
+        .encrypt_to_keys_seipdv1(&mut rng, SymmetricKeyAlgorithm::AES256, &[subkey])
+        .and_then(|message| message.to_armored_string(ArmorOptions::default()))
+        .map_err(encryption_error)
+}
+
+fn decrypt_openpgp(stored: &OpenpgpSecret, secret: &str) -> AppResult<Vec<u8>> {
+    let (key, _) = SignedSecretKey::from_string(&stored.key)
+        .map_err(|e| AppError::CorruptedConfig(format!("Stored OpenPGP key: {}", e)))?;
+    let (message, _) = Message::from_string(secret)
+        .map_err(|e| AppError::InvalidInput(format!("Not a valid OpenPGP message: {}", e)))?;
+
+    let passphrase = stored.passphrase.clone().unwrap_or_default();
+    let (decrypted, _) = message
+        .decrypt(|| passphrase, &[&key])
+        .map_err(|e| AppError::InvalidInput(format!("Failed to decrypt secret: {}", e)))?;
+
+    decrypted
+        .get_content()
+        .map_err(|e| AppError::InvalidInput(format!("Failed to decrypt secret: {}", e)))?
+        .ok_or_else(|| AppError::InvalidInput("Encrypted message is empty".to_string()))
+}
+
+fn invalid_key(e: pgp::errors::Error) -> AppError {
+    AppError::InvalidInput(format!("Invalid OpenPGP key: {}", e))
+}
+
+fn encryption_error(e: impl std::fmt::Display) -> AppError {
+    AppError::InvalidInput(format!("Failed to encrypt secret: {}", e))
+}
+
+impl KeyKind {
+    fn label(self) -> &'static str {
+        match self {
+            KeyKind::Age => "age",
+            KeyKind::Openpgp => "OpenPGP",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_age_round_trip() {
+        let identity = age::x25519::Identity::generate();
+        let recipient = RecipientKey {
+            name: "alice".to_string(),
+            kind: check_public_key(&identity.to_public().to_string()).unwrap(),
+            public_key: identity.to_public().to_string(),
+            added_at: 0,
+        };
+
+        let sealed = encrypt(&recipient, "db password").unwrap();
+        assert_eq!(encrypted_with(&sealed), Some(KeyKind::Age));
+        assert!(!sealed.contains("db password"));
+
+        let opened = age::decrypt(&identity, sealed.as_bytes()).unwrap();
+        assert_eq!(opened, b"db password");
+    }
+
+    #[test]
+    fn test_check_public_key() {
+        assert!(check_public_key("age1notarecipient").is_err());
+        assert!(check_public_key("ssh-ed25519 AAAA").is_err());
+        assert!(check_public_key(
+            "-----BEGIN PGP PUBLIC KEY BLOCK-----\n\nnot a key\n-----END PGP PUBLIC KEY BLOCK-----"
+        )
+        .is_err());
+        assert_eq!(encrypted_with("plain secret"), None);
+        assert_eq!(
+            encrypted_with("-----BEGIN PGP MESSAGE-----\n..."),
+            Some(KeyKind::Openpgp)
+        );
+    }
+}
@@ -13,6 +13,15 @@ pub enum AppError {
     #[error("Network error: {0}")]
     Network(String),
 
+    #[error("Proxy error: {0}")]
+    Proxy(String),
+
+    #[error("Certificate error: {0}")]
+    Certificate(String),
+
+    #[error("TLS handshake failed: {0}")]
+    Handshake(String),
+
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
 
@@ -24,6 +33,19 @@ pub enum AppError {
 
     #[error("Configuration error: {0}")]
     Configuration(String),
+
+    #[error("Clipboard error: {0}")]
+    Clipboard(String),
+
+    #[error("Stored configuration is corrupted: {0}")]
+    CorruptedConfig(String),
+
+    /// The API answered 429, with the seconds to wait if it said
+    #[error("API rate limit reached")]
+    RateLimited(Option<u64>),
+
+    #[error("Operation was cancelled")]
+    Cancelled,
 }
 
 impl From<reqwest::Error> for AppError {
@@ -38,6 +60,12 @@ impl From<keyring::Error> for AppError {
     }
 }
 
+impl From<std::io::Error> for AppError {
+    fn from(err: std::io::Error) -> Self {
+        AppError::Storage(err.to_string())
+    }
+}
+
 impl From<url::ParseError> for AppError {
     fn from(err: url::ParseError) -> Self {
         AppError::InvalidInput(format!("Invalid URL: {}", err))
@@ -57,10 +85,17 @@ impl From<AppError> for ErrorResponse {
             AppError::Storage(_) => "Storage",
             AppError::Api(_) => "Api",
             AppError::Network(_) => "Network",
+            AppError::Proxy(_) => "Proxy",
+            AppError::Certificate(_) => "Certificate",
+            AppError::Handshake(_) => "Handshake",
             AppError::Serialization(_) => "Serialization",
             AppError::InvalidInput(_) => "InvalidInput",
             AppError::Authentication(_) => "Authentication",
             AppError::Configuration(_) => "Configuration",
+            AppError::Clipboard(_) => "Clipboard",
+            AppError::CorruptedConfig(_) => "CorruptedConfig",
+            AppError::RateLimited(_) => "RateLimited",
+            AppError::Cancelled => "Cancelled",
         };
 
         ErrorResponse {
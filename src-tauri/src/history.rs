@@ -0,0 +1,422 @@
+use crate::api::CreateSecretResponse;
+use crate::error::{AppError, AppResult};
+use crate::incoming::{IncomingRequest, RequestStatus};
+use crate::store;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// File name of the receipt history in the app data directory
+pub const HISTORY_FILE: &str = "history.json";
+
+/// Receipt for a secret created from this app
+///
+/// The secret itself is never stored, only what is needed to share the
+/// link again and look up its status.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub metadata_key: String,
+    pub link: String,
+    pub profile: String,
+    pub ttl: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<String>,
+    /// Unix timestamp in seconds
+    pub created_at: u64,
+    /// Shared by receipts created together, e.g. the shares of a split secret
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// Last known state of the secret, e.g. `received` or `burned`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    /// Set when the link asks someone to send the user a secret
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request: Option<IncomingRequest>,
+}
+
+impl HistoryEntry {
+    pub fn new(
+        profile: &str,
+        response: &CreateSecretResponse,
+        ttl: u32,
+        recipient: Option<String>,
+    ) -> Self {
+        Self {
+            metadata_key: response.metadata_key.clone(),
+            link: response.link.clone(),
+            profile: profile.to_string(),
+            ttl,
+            recipient,
+            created_at: now(),
+            group: None,
+            state: None,
+            request: None,
+        }
+    }
+
+    pub fn with_group(mut self, group: Option<&str>) -> Self {
+        self.group = group.map(str::to_string);
+        self
+    }
+
+    pub fn with_request(mut self, request: IncomingRequest) -> Self {
+        self.request = Some(request);
+        self
+    }
+
+    /// Whether the secret has outlived its TTL
+    pub fn is_expired(&self) -> bool {
+        now() >= self.created_at + u64::from(self.ttl)
+    }
+
+    /// Whether the secret may still be waiting to be viewed
+    pub fn is_pending(&self) -> bool {
+        !self.is_expired()
+            && !matches!(
+                self.state.as_deref(),
+                Some("received" | "viewed" | "burned" | "expired")
+            )
+    }
+}
+
+/// Receipt history persisted as JSON, newest entry last
+pub struct History {
+    path: PathBuf,
+    entries: Mutex<Vec<HistoryEntry>>,
+}
+
+impl History {
+    /// Load history from disk, starting empty if the file is missing or unreadable
+    ///
+    /// An unreadable file is moved to `<name>.bak` first, so the receipts in
+    /// it, including open requests whose keys are in the keychain, are not
+    /// overwritten by the next write.
+    pub fn load(path: PathBuf) -> Self {
+        let entries = match store::read_json(&path) {
+            Ok(entries) => entries.unwrap_or_default(),
+            Err(e) => {
+                match store::set_aside(&path) {
+                    Ok(backup) => log::warn!(
+                        "Failed to load history, moved it to {} and starting empty: {}",
+                        backup.display(),
+                        e
+                    ),
+                    Err(move_error) => log::error!(
+                        "Failed to load history ({}) or move it aside, starting empty: {}",
+                        e,
+                        move_error
+                    ),
+                }
+                Vec::new()
+            }
+        };
+
+        Self {
+            path,
+            entries: Mutex::new(entries),
+        }
+    }
+
+    /// Add a receipt and persist the history
+    pub fn record(&self, entry: HistoryEntry) -> AppResult<()> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|e| e.metadata_key != entry.metadata_key);
+        entries.push(entry);
+        store::write_json(&self.path, &*entries)
+    }
+
+    /// Most recent entries first, at most `limit` of them
+    pub fn recent(&self, limit: usize) -> Vec<HistoryEntry> {
+        let entries = self.entries.lock().unwrap();
+        entries.iter().rev().take(limit).cloned().collect()
+    }
+
+    /// Add receipts from another install, skipping ones already present
+    /// Returns how many entries were added
+    pub fn merge(&self, incoming: Vec<HistoryEntry>) -> AppResult<usize> {
+        let mut entries = self.entries.lock().unwrap();
+        let before = entries.len();
+
+        for entry in incoming {
+            if !entries.iter().any(|e| e.metadata_key == entry.metadata_key) {
+                entries.push(entry);
+            }
+        }
+
+        let added = entries.len() - before;
+        if added > 0 {
+            entries.sort_by_key(|e| e.created_at);
+            store::write_json(&self.path, &*entries)?;
+        }
+
+        Ok(added)
+    }
+
+    /// Drop entries older than `retention_days`, where 0 keeps everything
+    /// Open requests are kept, as their key in the keychain is only deleted
+    /// once they are answered or cancelled
+    pub fn prune(&self, retention_days: u32) -> AppResult<()> {
+        if retention_days == 0 {
+            return Ok(());
+        }
+
+        let cutoff = now().saturating_sub(u64::from(retention_days) * 86400);
+        let mut entries = self.entries.lock().unwrap();
+        let before = entries.len();
+        entries.retain(|e| {
+            e.created_at >= cutoff
+                || e.request
+                    .as_ref()
+                    .is_some_and(|request| request.status == RequestStatus::Open)
+        });
+
+        if entries.len() == before {
+            return Ok(());
+        }
+        store::write_json(&self.path, &*entries)
+    }
+
+    /// Entries created together under `group`, oldest first
+    pub fn group(&self, group: &str) -> Vec<HistoryEntry> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .iter()
+            .filter(|e| e.group.as_deref() == Some(group))
+            .cloned()
+            .collect()
+    }
+
+    /// Store the latest known states, keyed by metadata key
+    pub fn set_states(&self, states: &[(String, String)]) -> AppResult<()> {
+        let mut entries = self.entries.lock().unwrap();
+        let mut changed = false;
+        for entry in entries.iter_mut() {
+            if let Some((_, state)) = states.iter().find(|(key, _)| *key == entry.metadata_key) {
+                if entry.state.as_ref() != Some(state) {
+                    entry.state = Some(state.clone());
+                    changed = true;
+                }
+            }
+        }
+
+        if !changed {
+            return Ok(());
+        }
+        store::write_json(&self.path, &*entries)
+    }
+
+    /// Receipts that haven't expired or been viewed or burned, oldest first
+    pub fn pending(&self) -> Vec<HistoryEntry> {
+        let entries = self.entries.lock().unwrap();
+        entries.iter().filter(|e| e.is_pending()).cloned().collect()
+    }
+
+    /// Links asking for a secret, newest first
+    pub fn requests(&self) -> Vec<HistoryEntry> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .iter()
+            .rev()
+            .filter(|e| e.request.is_some())
+            .cloned()
+            .collect()
+    }
+
+    /// Requests still waiting for a reply
+    pub fn open_requests(&self) -> Vec<IncomingRequest> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .iter()
+            .filter_map(|e| e.request.clone())
+            .filter(|request| request.status == RequestStatus::Open)
+            .collect()
+    }
+
+    /// Mark an open request as answered or cancelled
+    pub fn close_request(&self, id: &str, status: RequestStatus) -> AppResult<()> {
+        let mut entries = self.entries.lock().unwrap();
+        let request = entries
+            .iter_mut()
+            .filter_map(|e| e.request.as_mut())
+            .find(|request| request.id == id)
+            .ok_or_else(|| AppError::InvalidInput(format!("Unknown request: {}", id)))?;
+
+        if request.status != RequestStatus::Open {
+            return Err(AppError::InvalidInput(format!(
+                "Request {} is already closed",
+                id
+            )));
+        }
+        request.status = status;
+        request.closed_at = Some(now());
+        store::write_json(&self.path, &*entries)
+    }
+
+    /// Look up an entry by its metadata key
+    pub fn get(&self, metadata_key: &str) -> Option<HistoryEntry> {
+        let entries = self.entries.lock().unwrap();
+        entries.iter().find(|e| e.metadata_key == metadata_key).cloned()
+    }
+}
+
+/// Current Unix time in seconds
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(key: &str) -> CreateSecretResponse {
+        CreateSecretResponse {
+            link: format!("https://onetimesecret.com/secret/s{}", key),
+            secret_key: format!("s{}", key),
+            metadata_key: key.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_record_and_reload() {
+        let path = std::env::temp_dir().join(format!("ots-history-{}.json", std::process::id()));
+
+        let history = History::load(path.clone());
+        history
+            .record(HistoryEntry::new("default", &response("a"), 3600, None))
+            .unwrap();
+        history
+            .record(HistoryEntry::new("default", &response("b"), 60, None).with_group(Some("g1")))
+            .unwrap();
+
+        let reloaded = History::load(path.clone());
+        let recent = reloaded.recent(1);
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].metadata_key, "b");
+        assert!(reloaded.get("a").is_some());
+        assert_eq!(reloaded.group("g1"), vec![recent[0].clone()]);
+
+        assert_eq!(reloaded.pending().len(), 2);
+        reloaded
+            .set_states(&[("a".to_string(), "received".to_string())])
+            .unwrap();
+        assert_eq!(reloaded.get("a").unwrap().state.as_deref(), Some("received"));
+        assert_eq!(reloaded.pending(), vec![reloaded.get("b").unwrap()]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_load_sets_corrupt_file_aside() {
+        let dir = std::env::temp_dir().join(format!("ots-history-corrupt-{}", std::process::id()));
+        let path = dir.join("history.json");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&path, "[{\"metadata_key\": ").unwrap();
+
+        let history = History::load(path.clone());
+        assert!(history.recent(10).is_empty());
+        history
+            .record(HistoryEntry::new("default", &response("a"), 3600, None))
+            .unwrap();
+
+        let backup = dir.join("history.json.bak");
+        assert_eq!(
+            std::fs::read_to_string(&backup).unwrap(),
+            "[{\"metadata_key\": "
+        );
+        assert!(History::load(path).get("a").is_some());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_close_request() {
+        let path = std::env::temp_dir().join(format!("ots-history-requests-{}.json", std::process::id()));
+
+        let history = History::load(path.clone());
+        let request = IncomingRequest {
+            id: "request-1".to_string(),
+            public_key: "age1example".to_string(),
+            note: None,
+            status: RequestStatus::Open,
+            closed_at: None,
+        };
+        history
+            .record(HistoryEntry::new("default", &response("a"), 3600, None).with_request(request))
+            .unwrap();
+        history
+            .record(HistoryEntry::new("default", &response("b"), 3600, None))
+            .unwrap();
+        assert_eq!(history.requests().len(), 1);
+        assert_eq!(history.open_requests().len(), 1);
+
+        history.close_request("request-1", RequestStatus::Fulfilled).unwrap();
+        assert!(history.open_requests().is_empty());
+        assert!(history.close_request("request-1", RequestStatus::Cancelled).is_err());
+        assert!(history.close_request("request-2", RequestStatus::Cancelled).is_err());
+
+        let reloaded = History::load(path.clone());
+        let closed = reloaded.get("a").unwrap().request.unwrap();
+        assert_eq!(closed.status, RequestStatus::Fulfilled);
+        assert!(closed.closed_at.is_some());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_merge_and_prune() {
+        let path = std::env::temp_dir().join(format!("ots-history-prune-{}.json", std::process::id()));
+
+        let history = History::load(path.clone());
+        let mut old = HistoryEntry::new("default", &response("old"), 60, None);
+        old.created_at -= 8 * 86400;
+        history.record(old).unwrap();
+        history
+            .record(HistoryEntry::new("default", &response("new"), 60, None))
+            .unwrap();
+
+        let merged = history
+            .merge(vec![
+                HistoryEntry::new("default", &response("new"), 60, None),
+                HistoryEntry::new("work", &response("other"), 60, None),
+            ])
+            .unwrap();
+        assert_eq!(merged, 1);
+        assert_eq!(history.recent(1)[0].metadata_key, "other");
+
+        history.prune(0).unwrap();
+        assert_eq!(history.recent(10).len(), 3);
+
+        history.prune(7).unwrap();
+        let recent = history.recent(10);
+        assert_eq!(recent.len(), 2);
+        assert!(history.get("old").is_none());
+
+        let mut old_request = HistoryEntry::new("default", &response("asked"), 60, None)
+            .with_request(IncomingRequest {
+                id: "request-1".to_string(),
+                public_key: "age1example".to_string(),
+                note: None,
+                status: RequestStatus::Open,
+                closed_at: None,
+            });
+        old_request.created_at -= 8 * 86400;
+        history.record(old_request).unwrap();
+        history.prune(7).unwrap();
+        assert!(history.get("asked").is_some());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_is_expired() {
+        let mut entry = HistoryEntry::new("default", &response("a"), 60, None);
+        assert!(!entry.is_expired());
+
+        entry.created_at -= 61;
+        assert!(entry.is_expired());
+    }
+}
@@ -0,0 +1,172 @@
+use crate::error::{AppError, AppResult};
+use crate::quick_share;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Modifiers, Shortcut, ShortcutEvent, ShortcutState};
+
+/// Action triggered by a global hotkey
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyAction {
+    /// Share the clipboard contents as a secret
+    QuickShare,
+    /// Bring the main window to the front
+    ShowWindow,
+}
+
+impl HotkeyAction {
+    fn label(self) -> &'static str {
+        match self {
+            HotkeyAction::QuickShare => "quick share",
+            HotkeyAction::ShowWindow => "show window",
+        }
+    }
+}
+
+/// Accelerators for each action, e.g. `CmdOrCtrl+Shift+S`
+/// Hotkeys are off until the user assigns them
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HotkeySettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quick_share: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub show_window: Option<String>,
+}
+
+impl HotkeySettings {
+    fn accelerators(&self) -> Vec<(HotkeyAction, &str)> {
+        [
+            (HotkeyAction::QuickShare, &self.quick_share),
+            (HotkeyAction::ShowWindow, &self.show_window),
+        ]
+        .into_iter()
+        .filter_map(|(action, accelerator)| Some((action, accelerator.as_deref()?)))
+        .collect()
+    }
+}
+
+/// Shortcuts currently registered with the operating system
+#[derive(Default)]
+pub struct HotkeyRegistry {
+    bindings: Mutex<Vec<(HotkeyAction, Shortcut)>>,
+}
+
+impl HotkeyRegistry {
+    fn action_for(&self, shortcut: &Shortcut) -> Option<HotkeyAction> {
+        let bindings = self.bindings.lock().unwrap();
+        bindings
+            .iter()
+            .find(|(_, bound)| bound == shortcut)
+            .map(|(action, _)| *action)
+    }
+}
+
+/// Parse accelerators and reject bindings that conflict with each other
+pub fn resolve(settings: &HotkeySettings) -> AppResult<Vec<(HotkeyAction, Shortcut)>> {
+    let mut bindings: Vec<(HotkeyAction, Shortcut)> = Vec::new();
+
+    for (action, accelerator) in settings.accelerators() {
+        let shortcut: Shortcut = accelerator.parse().map_err(|e| {
+            AppError::InvalidInput(format!("Invalid shortcut '{}': {}", accelerator, e))
+        })?;
+
+        // A bare key would swallow normal typing system-wide
+        if shortcut.mods == Modifiers::empty() || shortcut.mods == Modifiers::SHIFT {
+            return Err(AppError::InvalidInput(format!(
+                "Shortcut '{}' needs a modifier such as Ctrl, Alt or Cmd",
+                accelerator
+            )));
+        }
+
+        if let Some((other, _)) = bindings.iter().find(|(_, bound)| *bound == shortcut) {
+            return Err(AppError::InvalidInput(format!(
+                "Shortcut '{}' is assigned to both {} and {}",
+                accelerator,
+                other.label(),
+                action.label()
+            )));
+        }
+
+        bindings.push((action, shortcut));
+    }
+
+    Ok(bindings)
+}
+
+/// Replace all registered hotkeys with the given settings
+///
+/// Registration fails when another application already owns a shortcut;
+/// in that case nothing stays registered and the conflict is reported.
+pub fn apply(app: &AppHandle, settings: &HotkeySettings) -> AppResult<()> {
+    let bindings = resolve(settings)?;
+    let global_shortcut = app.global_shortcut();
+    let registry = app.state::<HotkeyRegistry>();
+
+    global_shortcut.unregister_all().map_err(shortcut_error)?;
+    registry.bindings.lock().unwrap().clear();
+
+    for (_, shortcut) in &bindings {
+        if let Err(e) = global_shortcut.register(*shortcut) {
+            let _ = global_shortcut.unregister_all();
+            return Err(AppError::InvalidInput(format!(
+                "Shortcut '{}' is already in use by another application: {}",
+                shortcut, e
+            )));
+        }
+    }
+
+    *registry.bindings.lock().unwrap() = bindings;
+    Ok(())
+}
+
+/// Run the action bound to a pressed shortcut
+pub fn handle(app: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state() != ShortcutState::Pressed {
+        return;
+    }
+
+    match app.state::<HotkeyRegistry>().action_for(shortcut) {
+        Some(HotkeyAction::QuickShare) => quick_share::spawn(app),
+        Some(HotkeyAction::ShowWindow) => crate::focus_main_window(app),
+        None => {}
+    }
+}
+
+fn shortcut_error(err: tauri_plugin_global_shortcut::Error) -> AppError {
+    AppError::Configuration(format!("Global shortcut error: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(quick_share: Option<&str>, show_window: Option<&str>) -> HotkeySettings {
+        HotkeySettings {
+            quick_share: quick_share.map(String::from),
+            show_window: show_window.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_resolve_bindings() {
+        let bindings = resolve(&settings(Some("CmdOrCtrl+Shift+S"), Some("Alt+O"))).unwrap();
+        assert_eq!(bindings.len(), 2);
+        assert_eq!(bindings[0].0, HotkeyAction::QuickShare);
+
+        assert!(resolve(&HotkeySettings::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_resolve_rejects_conflicts() {
+        assert!(resolve(&settings(Some("Ctrl+Shift+S"), Some("Shift+Ctrl+S"))).is_err());
+    }
+
+    #[test]
+    fn test_resolve_rejects_invalid_shortcuts() {
+        assert!(resolve(&settings(Some("Ctrl+Nope"), None)).is_err());
+        assert!(resolve(&settings(Some("S"), None)).is_err());
+        assert!(resolve(&settings(Some("Shift+S"), None)).is_err());
+    }
+}
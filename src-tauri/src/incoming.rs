@@ -0,0 +1,279 @@
+use crate::api::CreateSecretRequest;
+use crate::encryption;
+use crate::error::{AppError, AppResult};
+use crate::recipients::{KeyKind, RecipientKey};
+use crate::storage::SecureStorage;
+use age::secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+
+/// First line of a request secret, followed by the request ID and age recipient
+const REQUEST_PREFIX: &str = "ots-request:";
+
+/// Line between the note and the instructions for senders without the app
+const INSTRUCTIONS_SEPARATOR: &str = "\n---\n";
+
+/// Longest accepted note
+const MAX_NOTE_LENGTH: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestStatus {
+    /// Waiting for the reply; the private key is in the keychain
+    Open,
+    /// The reply was retrieved and decrypted
+    Fulfilled,
+    Cancelled,
+}
+
+/// A request for someone to send the user a secret, kept in the history
+/// with the link that carries it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IncomingRequest {
+    pub id: String,
+    /// The `age1…` recipient the reply is encrypted to
+    pub public_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    pub status: RequestStatus,
+    /// Unix timestamp in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub closed_at: Option<u64>,
+}
+
+impl IncomingRequest {
+    /// Start a request with a fresh age key, stored in the keychain for
+    /// this request only
+    pub fn open(note: Option<String>) -> AppResult<Self> {
+        let note = note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+        if note.as_ref().is_some_and(|n| {
+            n.chars().count() > MAX_NOTE_LENGTH || n.contains(INSTRUCTIONS_SEPARATOR.trim())
+        }) {
+            return Err(AppError::InvalidInput(format!(
+                "Note must be at most {} characters and not contain \"---\"",
+                MAX_NOTE_LENGTH
+            )));
+        }
+
+        let id = format!("request-{:016x}", rand::random::<u64>());
+        let identity = age::x25519::Identity::generate();
+        SecureStorage::save_request_key(&id, identity.to_string().expose_secret())?;
+
+        Ok(Self {
+            id,
+            public_key: identity.to_public().to_string(),
+            note,
+            status: RequestStatus::Open,
+            closed_at: None,
+        })
+    }
+
+    /// Secret sent to the person asked, readable in a browser too
+    pub fn body(&self) -> String {
+        let mut body = format!("{}{} {}\n", REQUEST_PREFIX, self.id, self.public_key);
+        if let Some(note) = &self.note {
+            body.push_str(&format!("\n{}\n", note));
+        }
+        body.push_str(INSTRUCTIONS_SEPARATOR);
+        body.push_str(&format!(
+            "Someone asked you to send them a secret. In Onetimesecret Desktop, \
+             retrieve this link and answer the request. Without the app, encrypt \
+             the secret with `age --armor -r {}`, share the output as a one-time \
+             secret and send back its link.\n",
+            self.public_key
+        ));
+        body
+    }
+}
+
+/// Request to ask someone for a secret
+#[derive(Debug, Deserialize)]
+pub struct NewIncomingRequest {
+    /// What is being asked for, shown to the person asked
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Seconds the request link stays valid
+    pub ttl: u32,
+    #[serde(default)]
+    pub passphrase: Option<String>,
+    /// Email address to send the request link to
+    #[serde(default)]
+    pub recipient: Option<String>,
+}
+
+/// A request found in a retrieved secret, for the person asked to answer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReceivedRequest {
+    pub id: String,
+    pub public_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// Parse a request secret, returning `None` for other secrets
+pub fn parse(secret: &str) -> Option<ReceivedRequest> {
+    let (header, rest) = secret.split_once('\n')?;
+    let (id, public_key) = header.strip_prefix(REQUEST_PREFIX)?.split_once(' ')?;
+    if encryption::check_public_key(public_key).ok()? != KeyKind::Age {
+        return None;
+    }
+
+    let note = rest
+        .split_once(INSTRUCTIONS_SEPARATOR)
+        .map_or(rest, |(note, _)| note)
+        .trim();
+    Some(ReceivedRequest {
+        id: id.to_string(),
+        public_key: public_key.to_string(),
+        note: (!note.is_empty()).then(|| note.to_string()),
+    })
+}
+
+/// The secret sent back in answer to a request
+#[derive(Debug, Deserialize)]
+pub struct RequestAnswer {
+    /// The `age1…` recipient from the request
+    pub public_key: String,
+    pub secret: String,
+    pub ttl: u32,
+    #[serde(default)]
+    pub passphrase: Option<String>,
+    /// Email address to notify of the answer
+    #[serde(default)]
+    pub recipient: Option<String>,
+}
+
+impl RequestAnswer {
+    /// Encrypt the secret to the request's key
+    pub fn into_request(self) -> AppResult<CreateSecretRequest> {
+        if encryption::check_public_key(&self.public_key)? != KeyKind::Age {
+            return Err(AppError::InvalidInput(
+                "Requests are answered to an age key".to_string(),
+            ));
+        }
+        if self.secret.is_empty() {
+            return Err(AppError::InvalidInput("Secret cannot be empty".to_string()));
+        }
+
+        let key = RecipientKey {
+            name: "request".to_string(),
+            kind: KeyKind::Age,
+            public_key: self.public_key,
+            added_at: 0,
+        };
+        Ok(CreateSecretRequest {
+            secret: encryption::encrypt(&key, &self.secret)?,
+            passphrase: self.passphrase.filter(|p| !p.is_empty()),
+            ttl: self.ttl,
+            recipient: self.recipient,
+        })
+    }
+}
+
+/// Decrypt an age secret with the key of one of the open requests,
+/// returning the request ID and the plaintext
+///
+/// `None` means the secret answers none of them.
+pub fn decrypt_reply(
+    secret: &str,
+    open: &[IncomingRequest],
+) -> AppResult<Option<(String, String)>> {
+    let mut identities = Vec::new();
+    for request in open {
+        if let Some(key) = SecureStorage::load_request_key(&request.id)? {
+            let identity = key.parse::<age::x25519::Identity>().map_err(|e| {
+                AppError::CorruptedConfig(format!("Stored key of {}: {}", request.id, e))
+            })?;
+            identities.push((request.id.clone(), identity));
+        }
+    }
+
+    decrypt_with(secret, &identities)
+}
+
+fn decrypt_with(
+    secret: &str,
+    identities: &[(String, age::x25519::Identity)],
+) -> AppResult<Option<(String, String)>> {
+    for (id, identity) in identities {
+        let plaintext = match age::decrypt(identity, secret.as_bytes()) {
+            Ok(plaintext) => plaintext,
+            Err(age::DecryptError::NoMatchingKeys) => continue,
+            Err(e) => {
+                return Err(AppError::InvalidInput(format!(
+                    "Failed to decrypt secret: {}",
+                    e
+                )))
+            }
+        };
+
+        let plaintext = String::from_utf8(plaintext)
+            .map_err(|_| AppError::InvalidInput("Decrypted secret is not text".to_string()))?;
+        return Ok(Some((id.clone(), plaintext)));
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_body_round_trip() {
+        let identity = age::x25519::Identity::generate();
+        let request = IncomingRequest {
+            id: "request-1".to_string(),
+            public_key: identity.to_public().to_string(),
+            note: Some("Staging database password\nfor the migration".to_string()),
+            status: RequestStatus::Open,
+            closed_at: None,
+        };
+
+        let received = parse(&request.body()).unwrap();
+        assert_eq!(received.id, "request-1");
+        assert_eq!(received.public_key, request.public_key);
+        assert_eq!(received.note, request.note);
+
+        let without_note = IncomingRequest {
+            note: None,
+            ..request
+        };
+        assert_eq!(parse(&without_note.body()).unwrap().note, None);
+
+        assert_eq!(parse("ots-request:request-1 not-a-key\n"), None);
+        assert_eq!(parse("hunter2"), None);
+    }
+
+    #[test]
+    fn test_answer_decrypts_with_request_key() {
+        let ours = age::x25519::Identity::generate();
+        let other = age::x25519::Identity::generate();
+        let answer = RequestAnswer {
+            public_key: ours.to_public().to_string(),
+            secret: "s3cret".to_string(),
+            ttl: 3600,
+            passphrase: Some(String::new()),
+            recipient: None,
+        };
+
+        let request = answer.into_request().unwrap();
+        assert_eq!(request.passphrase, None);
+        assert_eq!(
+            encryption::encrypted_with(&request.secret),
+            Some(KeyKind::Age)
+        );
+
+        let identities = vec![
+            ("request-other".to_string(), other),
+            ("request-ours".to_string(), ours),
+        ];
+        assert_eq!(
+            decrypt_with(&request.secret, &identities).unwrap(),
+            Some(("request-ours".to_string(), "s3cret".to_string()))
+        );
+        assert_eq!(
+            decrypt_with(&request.secret, &identities[..1]).unwrap(),
+            None
+        );
+    }
+}
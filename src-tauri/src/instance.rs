@@ -0,0 +1,178 @@
+use crate::deep_link::{self, DeepLinkAction};
+use crate::resolve;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Event emitted to the frontend when files are queued for sharing
+pub const OPEN_FILES_EVENT: &str = "open-files";
+
+/// Work requested through command-line arguments
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct LaunchRequest {
+    pub links: Vec<DeepLinkAction>,
+    pub files: Vec<PathBuf>,
+}
+
+/// Files passed on the command line, waiting for the frontend
+#[derive(Default)]
+pub struct PendingFiles {
+    pending: Mutex<Vec<PathBuf>>,
+}
+
+impl PendingFiles {
+    pub fn push(&self, path: PathBuf) {
+        let mut pending = self.pending.lock().unwrap();
+        if !pending.contains(&path) {
+            pending.push(path);
+        }
+    }
+
+    pub fn take(&self) -> Vec<PathBuf> {
+        std::mem::take(&mut *self.pending.lock().unwrap())
+    }
+}
+
+/// Sort command-line arguments into secret links and files
+///
+/// `args` excludes the executable. Relative paths are resolved against
+/// `cwd`, the working directory of the launching process. `base_url` is
+/// the configured instance, used to recognize web links to secrets.
+/// `onetimesecret://` links are left to the deep-link plugin.
+pub fn parse_args(args: &[String], cwd: &Path, base_url: Option<&str>) -> LaunchRequest {
+    let mut request = LaunchRequest::default();
+
+    for arg in args {
+        if arg.starts_with('-') || arg.starts_with(&format!("{}:", deep_link::SCHEME)) {
+            continue;
+        }
+
+        if arg.starts_with("https://") || arg.starts_with("http://") {
+            let Some(base_url) = base_url else {
+                log::warn!("Ignoring link argument: no instance configured");
+                continue;
+            };
+            match deep_link::parse_web_link(arg, base_url) {
+                Ok(action) => request.links.push(action),
+                Err(e) => log::warn!("Ignoring link argument: {}", e),
+            }
+            continue;
+        }
+
+        let path = cwd.join(arg);
+        if path.is_file() {
+            request.files.push(path);
+        } else {
+            log::warn!("Ignoring argument that is not a file: {}", path.display());
+        }
+    }
+
+    request
+}
+
+/// Hand command-line arguments to the frontend
+///
+/// Used both for the arguments of this process at startup and for those a
+/// second launch forwards to the running instance.
+pub fn forward(app: &AppHandle, argv: &[String], cwd: &Path) {
+    let args = argv.get(1..).unwrap_or_default();
+    if args.is_empty() {
+        return;
+    }
+
+    let base_url = match resolve::api_config() {
+        Ok(config) => config.map(|c| c.base_url),
+        Err(e) => {
+            log::warn!("Failed to load configuration for link arguments: {}", e);
+            None
+        }
+    };
+
+    let request = parse_args(args, cwd, base_url.as_deref());
+    deep_link::enqueue(app, request.links);
+
+    queue_files(app, request.files);
+}
+
+/// Queue files for the share flow, from arguments or dropped on the window
+pub fn queue_files(app: &AppHandle, files: Vec<PathBuf>) {
+    if files.is_empty() {
+        return;
+    }
+
+    let pending = app.state::<PendingFiles>();
+    for path in files {
+        pending.push(path);
+    }
+
+    if let Err(e) = app.emit(OPEN_FILES_EVENT, ()) {
+        log::warn!("Failed to notify frontend of files: {}", e);
+    }
+}
+
+/// Drop folders and anything else that cannot be shared as a file
+pub fn dropped_files(paths: &[PathBuf]) -> Vec<PathBuf> {
+    paths
+        .iter()
+        .filter(|path| {
+            let is_file = path.is_file();
+            if !is_file {
+                log::warn!("Ignoring dropped item that is not a file: {}", path.display());
+            }
+            is_file
+        })
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args_links() {
+        let request = parse_args(
+            &args(&[
+                "https://onetimesecret.com/secret/abc123",
+                "https://elsewhere.example/secret/abc123",
+                "onetimesecret://secret/def456",
+            ]),
+            Path::new("/"),
+            Some("https://onetimesecret.com"),
+        );
+
+        assert_eq!(
+            request.links,
+            vec![DeepLinkAction::Retrieve {
+                key: "abc123".to_string()
+            }]
+        );
+        assert!(request.files.is_empty());
+    }
+
+    #[test]
+    fn test_parse_args_files() {
+        let dir = std::env::temp_dir();
+        let name = format!("ots-launch-{}.txt", std::process::id());
+        std::fs::write(dir.join(&name), "content").unwrap();
+
+        let request = parse_args(&args(&[&name, "--flag", "missing-file"]), &dir, None);
+        std::fs::remove_file(dir.join(&name)).unwrap();
+
+        assert_eq!(request.files, vec![dir.join(&name)]);
+        assert!(request.links.is_empty());
+    }
+
+    #[test]
+    fn test_pending_files_deduplicates() {
+        let pending = PendingFiles::default();
+        pending.push(PathBuf::from("/tmp/a"));
+        pending.push(PathBuf::from("/tmp/a"));
+        assert_eq!(pending.take().len(), 1);
+        assert!(pending.take().is_empty());
+    }
+}
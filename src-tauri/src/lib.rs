@@ -1,32 +1,216 @@
 // Modules
 mod api;
+mod attachment;
+mod batch;
+mod bulk;
+mod bundle;
+mod chunks;
+mod clipboard;
 mod commands;
+mod csp;
+mod deep_link;
+mod encryption;
 mod error;
+mod history;
+mod hotkeys;
+mod incoming;
+mod instance;
+mod operations;
+mod passphrase;
+mod qr;
+mod quick_share;
+mod recipients;
+mod resolve;
+mod settings;
+mod shamir;
 mod storage;
+mod store;
+mod templates;
+mod tray;
 
+use attachment::ReceivedFiles;
+use clipboard::ClipboardManager;
 use commands::*;
+use csp::ConnectSources;
+use deep_link::DeepLinkQueue;
+use history::History;
+use hotkeys::HotkeyRegistry;
+use instance::PendingFiles;
+use operations::Operations;
+use recipients::Recipients;
+use settings::SettingsStore;
+use templates::Templates;
+use std::path::Path;
+use tauri::{AppHandle, DragDropEvent, Manager, WebviewWindowBuilder, WindowEvent};
+use tray::TrayState;
+use tauri_plugin_deep_link::DeepLinkExt;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-  tauri::Builder::default()
+  // `config show --resolved` prints the effective API configuration and exits
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  if args == ["config", "show", "--resolved"] {
+    attach_console();
+    match resolve::ResolvedConfig::load() {
+      Ok(resolved) => print!("{}", resolved.report()),
+      Err(e) => {
+        eprintln!("{}", e);
+        std::process::exit(1);
+      }
+    }
+    return;
+  }
+
+  let mut builder = tauri::Builder::default();
+
+  // Must be the first plugin so a second launch exits before initializing
+  // anything else. Deep links in its arguments reach the deep-link plugin,
+  // everything else is forwarded to the running instance.
+  #[cfg(desktop)]
+  {
+    builder = builder.plugin(tauri_plugin_single_instance::init(|app, argv, cwd| {
+      log::info!("Second launch detected, forwarding arguments");
+      focus_main_window(app);
+      instance::forward(app, &argv, Path::new(&cwd));
+    }));
+    builder = builder.plugin(
+      tauri_plugin_global_shortcut::Builder::new()
+        .with_handler(hotkeys::handle)
+        .build(),
+    );
+  }
+
+  builder
     .plugin(
       tauri_plugin_log::Builder::default()
         .level(log::LevelFilter::Info)
         .build(),
     )
     .plugin(tauri_plugin_shell::init())
+    .plugin(tauri_plugin_dialog::init())
+    .plugin(tauri_plugin_deep_link::init())
+    .manage(ClipboardManager::default())
+    .manage(DeepLinkQueue::default())
+    .manage(PendingFiles::default())
+    .manage(TrayState::default())
+    .manage(HotkeyRegistry::default())
+    .manage(ReceivedFiles::default())
+    .manage(Operations::default())
     .invoke_handler(tauri::generate_handler![
       load_api_config,
       save_api_config,
       test_api_connection,
       create_secret,
+      create_chunked_secret,
       retrieve_secret,
+      share_file,
+      share_files,
+      preview_files,
+      save_received_file,
+      discard_received_file,
+      split_secret,
+      combine_shares,
+      bulk_create,
+      export_bulk_report,
+      run_batch,
+      cancel_operation,
       get_secret_metadata,
       clear_api_config,
+      show_resolved_config,
+      recover_api_config,
+      copy_link,
+      copy_secret,
+      clear_clipboard,
+      render_qr,
+      take_deep_links,
+      take_open_files,
+      list_profiles,
+      set_active_profile,
+      delete_profile,
+      list_history,
+      get_settings,
+      update_settings,
+      export_bundle,
+      import_bundle,
+      get_hotkeys,
+      update_hotkeys,
+      list_recipient_keys,
+      add_recipient_key,
+      remove_recipient_key,
+      get_own_keys,
+      generate_age_key,
+      import_openpgp_key,
+      delete_own_key,
+      list_templates,
+      save_template,
+      delete_template,
+      create_incoming_request,
+      list_incoming_requests,
+      cancel_incoming_request,
+      answer_incoming_request,
     ])
     .setup(|app| {
       log::info!("Onetimesecret Desktop v{} starting", env!("CARGO_PKG_VERSION"));
 
+      let config_dir = app.path().app_config_dir()?;
+      let data_dir = app.path().app_data_dir()?;
+      let settings = SettingsStore::load(config_dir.join(settings::SETTINGS_FILE));
+      let history = History::load(data_dir.join(history::HISTORY_FILE));
+      if let Err(e) = history.prune(settings.get().history_retention_days) {
+        log::warn!("Failed to prune history: {}", e);
+      }
+      app.manage(settings);
+      app.manage(history);
+      app.manage(Recipients::load(config_dir.join(recipients::RECIPIENTS_FILE)));
+      app.manage(Templates::load(config_dir.join(templates::TEMPLATES_FILE)));
+
+      // The main window is created here so its responses get the origins of
+      // the configured instances in `connect-src`
+      let sources = ConnectSources::default();
+      sources.set(csp::configured_sources());
+      app.manage(sources.clone());
+      WebviewWindowBuilder::from_config(app.handle(), &app.config().app.windows[0])?
+        .on_web_resource_request(move |_request, response| {
+          let headers = response.headers_mut();
+          let policy = headers
+            .get(csp::CSP_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(|policy| sources.apply(policy));
+          if let Some(value) = policy.and_then(|policy| policy.parse().ok()) {
+            headers.insert(csp::CSP_HEADER, value);
+          }
+        })
+        .build()?;
+
+      tray::init(app.handle())?;
+
+      #[cfg(desktop)]
+      {
+        let hotkeys = app.state::<SettingsStore>().get().hotkeys;
+        if let Err(e) = hotkeys::apply(app.handle(), &hotkeys) {
+          log::warn!("Failed to register global hotkeys: {}", e);
+        }
+      }
+
+      // Linux and unbundled Windows builds register the scheme at runtime
+      #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+      app.deep_link().register_all()?;
+
+      let handle = app.handle().clone();
+      app.deep_link().on_open_url(move |event| {
+        deep_link::dispatch(&handle, event.urls());
+      });
+
+      // Link that launched the app
+      if let Some(urls) = app.deep_link().get_current()? {
+        deep_link::dispatch(app.handle(), urls);
+      }
+
+      // Web links and files passed on the command line
+      let argv: Vec<String> = std::env::args().collect();
+      let cwd = std::env::current_dir().unwrap_or_default();
+      instance::forward(app.handle(), &argv, &cwd);
+
       #[cfg(debug_assertions)]
       {
         let window = app.get_webview_window("main").unwrap();
@@ -35,6 +219,44 @@ pub fn run() {
 
       Ok(())
     })
+    .on_window_event(|window, event| match event {
+      WindowEvent::CloseRequested { api, .. } => {
+        if window.state::<SettingsStore>().get().close_to_tray {
+          api.prevent_close();
+          let _ = window.hide();
+        }
+      }
+      // Files dropped on the window go through the same flow as "Open with"
+      WindowEvent::DragDrop(DragDropEvent::Drop { paths, .. }) => {
+        instance::queue_files(window.app_handle(), instance::dropped_files(paths));
+      }
+      _ => {}
+    })
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
 }
+
+/// Send output to the console of the shell that launched the app
+/// Release builds use the Windows GUI subsystem and start without one
+#[cfg(windows)]
+fn attach_console() {
+  use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+
+  // Fails when there is no parent console, e.g. when started from Explorer,
+  // leaving the exit code as the only result
+  unsafe {
+    AttachConsole(ATTACH_PARENT_PROCESS);
+  }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+/// Bring the main window to the front
+fn focus_main_window(app: &AppHandle) {
+  if let Some(window) = app.get_webview_window("main") {
+    let _ = window.unminimize();
+    let _ = window.show();
+    let _ = window.set_focus();
+  }
+}
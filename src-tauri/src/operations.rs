@@ -0,0 +1,170 @@
+use crate::error::{AppError, AppResult};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::watch;
+
+/// Event emitted as a long-running operation makes progress
+pub const OPERATION_PROGRESS_EVENT: &str = "operation-progress";
+
+/// Time after which a cancelled operation that is still running is logged
+const CANCEL_OVERDUE: Duration = Duration::from_secs(10);
+
+/// Payload of `OPERATION_PROGRESS_EVENT`
+#[derive(Debug, Clone, Serialize)]
+struct OperationProgress<'a, T> {
+    id: &'a str,
+    /// Steps finished so far, including failed ones
+    done: usize,
+    total: usize,
+    /// What the last step produced, e.g. the outcome of one batch receipt
+    #[serde(skip_serializing_if = "Option::is_none")]
+    item: Option<T>,
+}
+
+/// Handle given to a running operation
+pub struct Operation {
+    id: String,
+    app: AppHandle,
+    cancelled: watch::Receiver<bool>,
+}
+
+impl Operation {
+    /// Report that `done` of `total` steps are finished
+    pub fn progress<T: Serialize + Clone>(&self, done: usize, total: usize, item: Option<T>) {
+        let progress = OperationProgress {
+            id: &self.id,
+            done,
+            total,
+            item,
+        };
+        if let Err(e) = self.app.emit(OPERATION_PROGRESS_EVENT, progress) {
+            log::warn!("Failed to emit progress of {}: {}", self.id, e);
+        }
+    }
+
+    /// Whether the operation was asked to stop, for work that checks between
+    /// steps
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancelled.borrow()
+    }
+
+    /// Resolves once the operation is asked to stop
+    pub async fn cancelled(&self) {
+        cancel_requested(self.cancelled.clone()).await
+    }
+}
+
+/// Running operations, so they can be cancelled by ID
+#[derive(Default)]
+pub struct Operations {
+    running: Mutex<HashMap<String, watch::Sender<bool>>>,
+}
+
+impl Operations {
+    fn start(&self, id: &str) -> AppResult<watch::Receiver<bool>> {
+        let mut running = self.running.lock().unwrap();
+        if running.contains_key(id) {
+            return Err(AppError::InvalidInput(format!(
+                "An operation with ID {} is already running",
+                id
+            )));
+        }
+
+        let (sender, receiver) = watch::channel(false);
+        running.insert(id.to_string(), sender);
+        Ok(receiver)
+    }
+
+    /// Ask an operation to stop, returning whether it was running
+    pub fn cancel(&self, id: &str) -> bool {
+        let running = self.running.lock().unwrap();
+        running
+            .get(id)
+            .is_some_and(|sender| sender.send(true).is_ok())
+    }
+
+    fn finish(&self, id: &str) {
+        self.running.lock().unwrap().remove(id);
+    }
+}
+
+/// Run `work` as its own task under an operation ID, so `cancel_operation`
+/// can stop it
+///
+/// Callers pick the ID to cancel the operation before it returns; without
+/// one, a random ID is used. Once cancelled, `work` stops itself through
+/// `Operation::cancelled` and returns partial results. It is never aborted,
+/// as that would drop secrets the server already created without a receipt.
+pub async fn run<T, F, Fut>(app: &AppHandle, id: Option<String>, work: F) -> AppResult<T>
+where
+    F: FnOnce(Operation) -> Fut,
+    Fut: Future<Output = AppResult<T>> + Send + 'static,
+    T: Send + 'static,
+{
+    let id = id.unwrap_or_else(|| format!("op-{:016x}", rand::random::<u64>()));
+    let operations = app.state::<Operations>();
+    let cancelled = operations.start(&id)?;
+
+    let operation = Operation {
+        id: id.clone(),
+        app: app.clone(),
+        cancelled: cancelled.clone(),
+    };
+    let mut task = tokio::spawn(work(operation));
+
+    let joined = tokio::select! {
+        joined = &mut task => joined,
+        _ = cancel_requested(cancelled) => loop {
+            match tokio::time::timeout(CANCEL_OVERDUE, &mut task).await {
+                Ok(joined) => break joined,
+                Err(_) => log::warn!("Operation {} is still running after being cancelled", id),
+            }
+        }
+    };
+    operations.finish(&id);
+
+    flatten(joined)
+}
+
+fn flatten<T>(joined: Result<AppResult<T>, tokio::task::JoinError>) -> AppResult<T> {
+    match joined {
+        Ok(result) => result,
+        Err(e) if e.is_cancelled() => Err(AppError::Cancelled),
+        Err(e) => Err(AppError::Api(format!("Operation failed: {}", e))),
+    }
+}
+
+async fn cancel_requested(mut cancelled: watch::Receiver<bool>) {
+    while !*cancelled.borrow_and_update() {
+        if cancelled.changed().await.is_err() {
+            // Nobody can cancel anymore
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cancel() {
+        let operations = Operations::default();
+        let cancelled = operations.start("op1").unwrap();
+        assert!(operations.start("op1").is_err());
+        assert!(!operations.cancel("other"));
+
+        assert!(operations.cancel("op1"));
+        tokio::time::timeout(Duration::from_secs(1), cancel_requested(cancelled))
+            .await
+            .unwrap();
+
+        operations.finish("op1");
+        assert!(!operations.cancel("op1"));
+        assert!(operations.start("op1").is_ok());
+    }
+}
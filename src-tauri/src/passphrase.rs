@@ -0,0 +1,27 @@
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+
+/// Length of generated passphrases, about 119 bits of entropy
+const GENERATED_LENGTH: usize = 20;
+
+/// Generate a random alphanumeric passphrase
+pub fn generate() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(GENERATED_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let first = generate();
+        assert_eq!(first.len(), GENERATED_LENGTH);
+        assert!(first.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert_ne!(first, generate());
+    }
+}
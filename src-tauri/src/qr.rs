@@ -0,0 +1,185 @@
+use crate::error::{AppError, AppResult};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use qrcode::render::svg;
+use qrcode::{Color, EcLevel, QrCode};
+use serde::{Deserialize, Serialize};
+
+/// Width of the blank border around the code, in modules
+const QUIET_ZONE: usize = 4;
+
+/// Default size of a single module in the PNG output, in pixels
+const DEFAULT_MODULE_SIZE: u32 = 8;
+
+/// Largest accepted module size, which keeps PNG output reasonably small
+const MAX_MODULE_SIZE: u32 = 32;
+
+/// QR error correction level
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorCorrection {
+    /// Recovers about 7% of damaged modules
+    Low,
+    /// Recovers about 15% of damaged modules
+    #[default]
+    Medium,
+    /// Recovers about 25% of damaged modules
+    Quartile,
+    /// Recovers about 30% of damaged modules
+    High,
+}
+
+impl From<ErrorCorrection> for EcLevel {
+    fn from(level: ErrorCorrection) -> Self {
+        match level {
+            ErrorCorrection::Low => EcLevel::L,
+            ErrorCorrection::Medium => EcLevel::M,
+            ErrorCorrection::Quartile => EcLevel::Q,
+            ErrorCorrection::High => EcLevel::H,
+        }
+    }
+}
+
+/// Request to render a share link as a QR code
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QrRequest {
+    pub link: String,
+    #[serde(default)]
+    pub error_correction: ErrorCorrection,
+    /// Pixels per module in the PNG output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub module_size: Option<u32>,
+}
+
+/// Rendered QR code as data URIs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QrImage {
+    pub svg: String,
+    pub png: String,
+    /// Number of modules per side, excluding the quiet zone
+    pub width: usize,
+}
+
+/// Render a share link as SVG and PNG data URIs
+/// Rendering happens locally so the link never leaves the machine
+pub fn render(request: &QrRequest) -> AppResult<QrImage> {
+    let link = url::Url::parse(&request.link)?;
+    if !matches!(link.scheme(), "https" | "http") {
+        return Err(AppError::InvalidInput(
+            "Only web links can be rendered as QR codes".to_string(),
+        ));
+    }
+
+    let module_size = request.module_size.unwrap_or(DEFAULT_MODULE_SIZE);
+    if module_size == 0 || module_size > MAX_MODULE_SIZE {
+        return Err(AppError::InvalidInput(format!(
+            "Module size must be between 1 and {} pixels",
+            MAX_MODULE_SIZE
+        )));
+    }
+
+    let code = QrCode::with_error_correction_level(link.as_str(), request.error_correction.into())
+        .map_err(|e| AppError::InvalidInput(format!("Cannot encode link as QR code: {}", e)))?;
+
+    let svg = code
+        .render::<svg::Color>()
+        .quiet_zone(true)
+        .module_dimensions(module_size, module_size)
+        .build();
+
+    let png = encode_png(&code, module_size)?;
+
+    Ok(QrImage {
+        svg: format!("data:image/svg+xml;base64,{}", BASE64.encode(svg.as_bytes())),
+        png: format!("data:image/png;base64,{}", BASE64.encode(png)),
+        width: code.width(),
+    })
+}
+
+/// Encode the code as an 8-bit grayscale PNG
+fn encode_png(code: &QrCode, module_size: u32) -> AppResult<Vec<u8>> {
+    let width = code.width();
+    let colors = code.to_colors();
+    let scale = module_size as usize;
+    let side = (width + 2 * QUIET_ZONE) * scale;
+
+    let mut pixels = vec![0xFFu8; side * side];
+    for (index, color) in colors.iter().enumerate() {
+        if *color != Color::Dark {
+            continue;
+        }
+
+        let x = (index % width + QUIET_ZONE) * scale;
+        let y = (index / width + QUIET_ZONE) * scale;
+        for row in y..y + scale {
+            pixels[row * side + x..row * side + x + scale].fill(0x00);
+        }
+    }
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, side as u32, side as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header().map_err(png_error)?;
+    writer.write_image_data(&pixels).map_err(png_error)?;
+    writer.finish().map_err(png_error)?;
+
+    Ok(png)
+}
+
+fn png_error(err: png::EncodingError) -> AppError {
+    AppError::InvalidInput(format!("Failed to encode QR code: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(link: &str) -> QrRequest {
+        QrRequest {
+            link: link.to_string(),
+            error_correction: ErrorCorrection::default(),
+            module_size: None,
+        }
+    }
+
+    #[test]
+    fn test_render_link() {
+        let image = render(&request("https://onetimesecret.com/secret/abc123")).unwrap();
+        assert!(image.svg.starts_with("data:image/svg+xml;base64,"));
+        assert!(image.png.starts_with("data:image/png;base64,"));
+
+        let png = BASE64
+            .decode(image.png.trim_start_matches("data:image/png;base64,"))
+            .unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    }
+
+    #[test]
+    fn test_higher_error_correction_grows_code() {
+        let link = "https://onetimesecret.com/secret/abc123def456ghi789";
+        let low = render(&QrRequest {
+            error_correction: ErrorCorrection::Low,
+            ..request(link)
+        })
+        .unwrap();
+        let high = render(&QrRequest {
+            error_correction: ErrorCorrection::High,
+            ..request(link)
+        })
+        .unwrap();
+        assert!(high.width > low.width);
+    }
+
+    #[test]
+    fn test_render_rejects_invalid_input() {
+        assert!(render(&request("not-a-url")).is_err());
+        assert!(render(&request("javascript:alert(1)")).is_err());
+
+        let oversized = QrRequest {
+            module_size: Some(MAX_MODULE_SIZE + 1),
+            ..request("https://onetimesecret.com/secret/abc")
+        };
+        assert!(render(&oversized).is_err());
+    }
+}
@@ -0,0 +1,80 @@
+use crate::api::CreateSecretRequest;
+use crate::clipboard::{self, ClipboardManager};
+use crate::commands::share_secret;
+use crate::error::{AppError, AppResult};
+use crate::passphrase;
+use crate::settings::SettingsStore;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Event emitted after the clipboard contents were shared
+pub const QUICK_SHARE_EVENT: &str = "quick-share";
+
+/// Payload of the quick-share event
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QuickShareResult {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    /// Generated passphrase, to be sent to the recipient separately
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub passphrase: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Share the clipboard text in the background and report the outcome
+/// Used by the tray menu and the global hotkey
+pub fn spawn(app: &AppHandle) {
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = match share_clipboard(&handle).await {
+            Ok(result) => result,
+            Err(e) => {
+                log::warn!("Quick share failed: {}", e);
+                QuickShareResult {
+                    error: Some(e.to_string()),
+                    ..Default::default()
+                }
+            }
+        };
+
+        // The passphrase is only shown in the window
+        if result.passphrase.is_some() || !result.success {
+            crate::focus_main_window(&handle);
+        }
+        let _ = handle.emit(QUICK_SHARE_EVENT, result);
+    });
+}
+
+/// Share the clipboard text with the default TTL and recipient and copy the
+/// link back
+///
+/// When the settings require a passphrase, one is generated and returned
+/// instead of being placed on the clipboard next to the link.
+async fn share_clipboard(app: &AppHandle) -> AppResult<QuickShareResult> {
+    let secret = tokio::task::spawn_blocking(clipboard::read_text)
+        .await
+        .map_err(|e| AppError::Clipboard(e.to_string()))??;
+
+    let settings = app.state::<SettingsStore>().get();
+    let passphrase = settings.require_passphrase.then(passphrase::generate);
+
+    let request = CreateSecretRequest {
+        secret,
+        passphrase: passphrase.clone(),
+        ttl: settings.default_ttl,
+        recipient: settings.default_recipient.clone(),
+    };
+
+    let response = share_secret(app, &request).await?;
+    app.state::<ClipboardManager>()
+        .copy(&response.link, clipboard::clear_delay(settings.clipboard_clear_secs))?;
+
+    Ok(QuickShareResult {
+        success: true,
+        link: Some(response.link),
+        passphrase,
+        error: None,
+    })
+}
@@ -0,0 +1,141 @@
+use crate::encryption;
+use crate::error::{AppError, AppResult};
+use crate::history;
+use crate::store;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// File name of the recipient keyring in the app config directory
+pub const RECIPIENTS_FILE: &str = "recipients.json";
+
+/// Longest accepted recipient name
+const MAX_NAME_LENGTH: usize = 64;
+
+/// Kind of key a secret can be encrypted to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyKind {
+    Age,
+    Openpgp,
+}
+
+/// Public key of a known recipient
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecipientKey {
+    pub name: String,
+    pub kind: KeyKind,
+    /// `age1…` recipient or an armored OpenPGP public key
+    pub public_key: String,
+    /// Unix timestamp in seconds
+    pub added_at: u64,
+}
+
+/// Keyring of known recipients persisted as JSON
+///
+/// Only public keys are kept here; the user's own private keys live in
+/// the OS keychain.
+pub struct Recipients {
+    path: PathBuf,
+    keys: Mutex<Vec<RecipientKey>>,
+}
+
+impl Recipients {
+    /// Load the keyring, starting empty if the file is missing or unreadable
+    pub fn load(path: PathBuf) -> Self {
+        let keys = match store::read_json(&path) {
+            Ok(keys) => keys.unwrap_or_default(),
+            Err(e) => {
+                log::warn!("Failed to load recipient keys, starting empty: {}", e);
+                Vec::new()
+            }
+        };
+
+        Self {
+            path,
+            keys: Mutex::new(keys),
+        }
+    }
+
+    /// All known recipients, sorted by name
+    pub fn list(&self) -> Vec<RecipientKey> {
+        let mut keys = self.keys.lock().unwrap().clone();
+        keys.sort_by(|a, b| a.name.cmp(&b.name));
+        keys
+    }
+
+    pub fn get(&self, name: &str) -> AppResult<RecipientKey> {
+        let keys = self.keys.lock().unwrap();
+        keys.iter()
+            .find(|key| key.name == name)
+            .cloned()
+            .ok_or_else(|| AppError::InvalidInput(format!("Unknown recipient: {}", name)))
+    }
+
+    /// Add a recipient, replacing any key already stored under the name
+    pub fn add(&self, name: &str, public_key: &str) -> AppResult<RecipientKey> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+            return Err(AppError::InvalidInput(format!(
+                "Recipient name must be 1 to {} characters",
+                MAX_NAME_LENGTH
+            )));
+        }
+
+        let public_key = public_key.trim();
+        let key = RecipientKey {
+            name: name.to_string(),
+            kind: encryption::check_public_key(public_key)?,
+            public_key: public_key.to_string(),
+            added_at: history::now(),
+        };
+
+        let mut keys = self.keys.lock().unwrap();
+        keys.retain(|k| k.name != key.name);
+        keys.push(key.clone());
+        store::write_json(&self.path, &*keys)?;
+
+        Ok(key)
+    }
+
+    pub fn remove(&self, name: &str) -> AppResult<()> {
+        let mut keys = self.keys.lock().unwrap();
+        let before = keys.len();
+        keys.retain(|k| k.name != name);
+
+        if keys.len() == before {
+            return Err(AppError::InvalidInput(format!(
+                "Unknown recipient: {}",
+                name
+            )));
+        }
+        store::write_json(&self.path, &*keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_and_remove() {
+        let path = std::env::temp_dir().join(format!("ots-recipients-{}.json", std::process::id()));
+        let recipient = age::x25519::Identity::generate().to_public().to_string();
+
+        let recipients = Recipients::load(path.clone());
+        let added = recipients.add(" alice ", &recipient).unwrap();
+        assert_eq!(added.name, "alice");
+        assert_eq!(added.kind, KeyKind::Age);
+        assert!(recipients.add("bob", "not a key").is_err());
+        assert!(recipients.add("", &recipient).is_err());
+
+        let reloaded = Recipients::load(path.clone());
+        assert_eq!(reloaded.get("alice").unwrap().public_key, recipient);
+
+        reloaded.remove("alice").unwrap();
+        assert!(reloaded.get("alice").is_err());
+        assert!(reloaded.remove("alice").is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,390 @@
+use crate::error::{AppError, AppResult};
+use crate::storage::{ApiConfig, ProxySettings, SecureStorage, TlsSettings};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Environment variable overriding the API base URL
+pub const BASE_URL_VAR: &str = "OTS_BASE_URL";
+/// Environment variable overriding the account email
+pub const USERNAME_VAR: &str = "OTS_USERNAME";
+/// Environment variable overriding the API key
+pub const API_KEY_VAR: &str = "OTS_API_KEY";
+/// Environment variable pointing at a TOML config file
+pub const CONFIG_FILE_VAR: &str = "OTS_CONFIG";
+
+/// Where a configuration value came from, highest precedence first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigSource {
+    Environment,
+    ConfigFile,
+    Keychain,
+}
+
+impl ConfigSource {
+    fn label(self) -> &'static str {
+        match self {
+            ConfigSource::Environment => "environment",
+            ConfigSource::ConfigFile => "config file",
+            ConfigSource::Keychain => "keychain",
+        }
+    }
+}
+
+/// Values from a TOML config file, any of which may be left out
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileConfig {
+    base_url: Option<String>,
+    username: Option<String>,
+    api_key: Option<String>,
+    proxy: Option<ProxySettings>,
+    tls: Option<TlsSettings>,
+    allow_insecure_http: Option<bool>,
+}
+
+/// A single resolved value and its source
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Resolved {
+    value: String,
+    source: ConfigSource,
+}
+
+/// API configuration assembled field by field from all sources
+///
+/// Each field is taken from the first source that sets it: environment
+/// variables, then the config file named by `OTS_CONFIG`, then the active
+/// keychain profile. The keychain is only read when the base URL is not
+/// overridden, so its credentials are never sent to another host; such
+/// runs take the proxy and TLS settings from the config file or the defaults.
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedConfig {
+    profile: Option<String>,
+    config_file: Option<PathBuf>,
+    base_url: Option<Resolved>,
+    username: Option<Resolved>,
+    api_key: Option<Resolved>,
+    proxy: Option<(ProxySettings, ConfigSource)>,
+    tls: Option<(TlsSettings, ConfigSource)>,
+    allow_insecure_http: Option<(bool, ConfigSource)>,
+}
+
+/// One line of the resolved configuration report
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportField {
+    pub name: &'static str,
+    /// The value, or `********` for the API key
+    pub value: Option<String>,
+    pub source: Option<ConfigSource>,
+}
+
+/// Resolved configuration with each value's source and the key redacted
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigReport {
+    pub profile: Option<String>,
+    pub config_file: Option<String>,
+    pub fields: Vec<ReportField>,
+}
+
+impl ResolvedConfig {
+    /// Resolve from the process environment, config file and keychain
+    pub fn load() -> AppResult<Self> {
+        Self::resolve(|name| std::env::var(name).ok(), || {
+            let index = SecureStorage::profile_index()?;
+            Ok((index.active.clone(), SecureStorage::load_profile(&index.active)?))
+        })
+    }
+
+    fn resolve<E, K>(env: E, keychain: K) -> AppResult<Self>
+    where
+        E: Fn(&str) -> Option<String>,
+        K: FnOnce() -> AppResult<(String, Option<ApiConfig>)>,
+    {
+        let env = |name: &str| env(name).filter(|value| !value.trim().is_empty());
+
+        let mut resolved = Self::default();
+        resolved.fill(
+            ConfigSource::Environment,
+            env(BASE_URL_VAR),
+            env(USERNAME_VAR),
+            env(API_KEY_VAR),
+        );
+
+        if let Some(path) = env(CONFIG_FILE_VAR) {
+            let path = PathBuf::from(path);
+            let file = read_file(&path)?;
+            resolved.fill(ConfigSource::ConfigFile, file.base_url, file.username, file.api_key);
+            resolved.proxy = file.proxy.map(|proxy| (proxy, ConfigSource::ConfigFile));
+            resolved.tls = file.tls.map(|tls| (tls, ConfigSource::ConfigFile));
+            resolved.allow_insecure_http = file
+                .allow_insecure_http
+                .map(|allow| (allow, ConfigSource::ConfigFile));
+            resolved.config_file = Some(path);
+        }
+
+        if resolved.base_url.is_none() {
+            let (profile, config) = keychain()?;
+            if let Some(config) = config {
+                resolved.proxy = resolved
+                    .proxy
+                    .take()
+                    .or(Some((config.proxy, ConfigSource::Keychain)));
+                resolved.tls = resolved
+                    .tls
+                    .take()
+                    .or(Some((config.tls, ConfigSource::Keychain)));
+                resolved.allow_insecure_http = resolved
+                    .allow_insecure_http
+                    .take()
+                    .or(Some((config.allow_insecure_http, ConfigSource::Keychain)));
+                resolved.fill(
+                    ConfigSource::Keychain,
+                    Some(config.base_url),
+                    Some(config.username),
+                    config.api_key,
+                );
+            }
+            resolved.profile = Some(profile);
+        }
+
+        Ok(resolved)
+    }
+
+    /// Set fields that no higher-precedence source provided
+    fn fill(
+        &mut self,
+        source: ConfigSource,
+        base_url: Option<String>,
+        username: Option<String>,
+        api_key: Option<String>,
+    ) {
+        let resolved = |value: Option<String>| value.map(|value| Resolved { value, source });
+
+        self.base_url = self.base_url.take().or_else(|| resolved(base_url));
+        self.username = self.username.take().or_else(|| resolved(username));
+        self.api_key = self.api_key.take().or_else(|| resolved(api_key));
+    }
+
+    /// The configuration to use, or None when nothing is configured anywhere
+    /// Values from the environment or a file are validated like saved ones
+    pub fn into_api_config(self) -> AppResult<Option<ApiConfig>> {
+        let (base_url, username) = match (self.base_url, self.username) {
+            (Some(base_url), Some(username)) => (base_url, username),
+            (None, None) => return Ok(None),
+            (Some(base_url), None) => return Err(overridden_base_url(base_url.source)),
+            (None, Some(_)) => {
+                return Err(AppError::Configuration(format!(
+                    "Incomplete API configuration, missing base URL (set {})",
+                    BASE_URL_VAR
+                )))
+            }
+        };
+        if base_url.source != ConfigSource::Keychain && self.api_key.is_none() {
+            return Err(overridden_base_url(base_url.source));
+        }
+
+        let config = ApiConfig {
+            base_url: base_url.value,
+            username: username.value,
+            api_key: self.api_key.map(|key| key.value),
+            proxy: self.proxy.map(|(proxy, _)| proxy).unwrap_or_default(),
+            tls: self.tls.map(|(tls, _)| tls).unwrap_or_default(),
+            allow_insecure_http: self.allow_insecure_http.is_some_and(|(allow, _)| allow),
+        };
+        SecureStorage::validate_config(&config)?;
+
+        Ok(Some(config))
+    }
+
+    /// Describe where each value came from, never showing the API key
+    pub fn report(&self) -> ConfigReport {
+        let field = |name, resolved: &Option<Resolved>, redact: bool| ReportField {
+            name,
+            value: resolved.as_ref().map(|r| {
+                if redact {
+                    "********".to_string()
+                } else {
+                    r.value.clone()
+                }
+            }),
+            source: resolved.as_ref().map(|r| r.source),
+        };
+
+        ConfigReport {
+            profile: self.profile.clone(),
+            config_file: self.config_file.as_ref().map(|p| p.display().to_string()),
+            fields: vec![
+                field("base_url", &self.base_url, false),
+                field("username", &self.username, false),
+                field("api_key", &self.api_key, true),
+                ReportField {
+                    name: "proxy",
+                    value: self.proxy.as_ref().map(|(proxy, _)| match &proxy.url {
+                        Some(url) => format!("{:?} {}", proxy.mode, url).to_lowercase(),
+                        None => format!("{:?}", proxy.mode).to_lowercase(),
+                    }),
+                    source: self.proxy.as_ref().map(|(_, source)| *source),
+                },
+                ReportField {
+                    name: "tls",
+                    value: self.tls.as_ref().map(|(tls, _)| {
+                        format!(
+                            "{} CA file(s), {} pin(s)",
+                            tls.ca_certificates.len(),
+                            tls.spki_pins.len()
+                        )
+                    }),
+                    source: self.tls.as_ref().map(|(_, source)| *source),
+                },
+                ReportField {
+                    name: "insecure_http",
+                    value: self
+                        .allow_insecure_http
+                        .map(|(allow, _)| if allow { "allowed" } else { "off" }.to_string()),
+                    source: self.allow_insecure_http.map(|(_, source)| source),
+                },
+            ],
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "profile:     {}", self.profile.as_deref().unwrap_or("(not used)"))?;
+        writeln!(f, "config file: {}", self.config_file.as_deref().unwrap_or("(none)"))?;
+
+        for field in &self.fields {
+            let value = field.value.as_deref().unwrap_or("(not set)");
+            match field.source {
+                Some(source) => writeln!(f, "{:<12} {}  [{}]", field.name, value, source.label())?,
+                None => writeln!(f, "{:<12} {}", field.name, value)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A base URL set outside the keychain without the credentials to go with it
+fn overridden_base_url(source: ConfigSource) -> AppError {
+    AppError::Configuration(format!(
+        "Incomplete API configuration: the base URL from the {} also needs a username \
+         and API key there (or in {} and {}); keychain credentials are only used with \
+         the keychain base URL",
+        source.label(),
+        USERNAME_VAR,
+        API_KEY_VAR
+    ))
+}
+
+/// API configuration from all sources in precedence order
+pub fn api_config() -> AppResult<Option<ApiConfig>> {
+    ResolvedConfig::load()?.into_api_config()
+}
+
+fn read_file(path: &Path) -> AppResult<FileConfig> {
+    let contents = std::fs::read_to_string(path).map_err(|e| {
+        AppError::Configuration(format!("Cannot read config file {}: {}", path.display(), e))
+    })?;
+
+    toml::from_str(&contents).map_err(|e| {
+        AppError::Configuration(format!("Invalid config file {}: {}", path.display(), e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keychain() -> AppResult<(String, Option<ApiConfig>)> {
+        Ok((
+            "default".to_string(),
+            Some(ApiConfig {
+                base_url: "https://onetimesecret.com".to_string(),
+                username: "keychain@example.com".to_string(),
+                api_key: Some("keychain-key".to_string()),
+                ..Default::default()
+            }),
+        ))
+    }
+
+    #[test]
+    fn test_environment_overrides_keychain() {
+        let env = |name: &str| match name {
+            USERNAME_VAR => Some("ci@example.com".to_string()),
+            API_KEY_VAR => Some("".to_string()),
+            _ => None,
+        };
+
+        let resolved = ResolvedConfig::resolve(env, keychain).unwrap();
+        let report = resolved.report();
+        assert_eq!(report.fields[0].source, Some(ConfigSource::Keychain));
+        assert_eq!(report.fields[1].source, Some(ConfigSource::Environment));
+        assert_eq!(report.fields[2].value.as_deref(), Some("********"));
+        assert!(!report.to_string().contains("keychain-key"));
+
+        let config = resolved.into_api_config().unwrap().unwrap();
+        assert_eq!(config.username, "ci@example.com");
+        assert_eq!(config.api_key.as_deref(), Some("keychain-key"));
+    }
+
+    #[test]
+    fn test_config_file_and_skipped_keychain() {
+        let path = std::env::temp_dir().join(format!("ots-config-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            "base_url = \"https://eu.onetimesecret.com\"\nusername = \"file@example.com\"\n",
+        )
+        .unwrap();
+
+        let file = path.display().to_string();
+        let env = |name: &str| match name {
+            CONFIG_FILE_VAR => Some(file.clone()),
+            API_KEY_VAR => Some("env-key".to_string()),
+            _ => None,
+        };
+        let resolved =
+            ResolvedConfig::resolve(env, || panic!("keychain should not be read")).unwrap();
+        assert_eq!(resolved.report().profile, None);
+
+        let config = resolved.into_api_config().unwrap().unwrap();
+        assert_eq!(config.base_url, "https://eu.onetimesecret.com");
+        assert_eq!(config.api_key.as_deref(), Some("env-key"));
+
+        std::fs::write(&path, "base_url = \"https://eu.onetimesecret.com\"\nkey = \"x\"\n").unwrap();
+        assert!(ResolvedConfig::resolve(env, keychain).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_incomplete_config() {
+        let env = |name: &str| (name == BASE_URL_VAR).then(|| "https://onetimesecret.com".to_string());
+        let resolved = ResolvedConfig::resolve(env, || Ok(("default".to_string(), None))).unwrap();
+        assert!(resolved.into_api_config().is_err());
+
+        let resolved = ResolvedConfig::resolve(|_| None, || Ok(("default".to_string(), None))).unwrap();
+        assert!(resolved.into_api_config().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_overridden_base_url_skips_keychain_credentials() {
+        let env = |name: &str| (name == BASE_URL_VAR).then(|| "https://attacker.example".to_string());
+        let resolved =
+            ResolvedConfig::resolve(env, || panic!("keychain should not be read")).unwrap();
+        let report = resolved.report();
+        assert_eq!(report.fields[1].value, None);
+        assert_eq!(report.fields[2].value, None);
+
+        let error = resolved.into_api_config().unwrap_err().to_string();
+        assert!(error.contains(API_KEY_VAR), "{}", error);
+
+        let env = |name: &str| match name {
+            BASE_URL_VAR => Some("https://eu.onetimesecret.com".to_string()),
+            USERNAME_VAR => Some("ci@example.com".to_string()),
+            _ => None,
+        };
+        let resolved = ResolvedConfig::resolve(env, keychain).unwrap();
+        assert!(resolved.into_api_config().is_err());
+    }
+}
@@ -0,0 +1,312 @@
+use crate::clipboard::DEFAULT_CLEAR_AFTER_SECS;
+use crate::error::{AppError, AppResult};
+use crate::hotkeys::HotkeySettings;
+use crate::store;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+/// File name of the settings in the app config directory
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// Schema version written by this build
+pub const SETTINGS_VERSION: u32 = 3;
+
+/// Longest accepted clipboard clear timeout, in seconds
+const MAX_CLIPBOARD_CLEAR_SECS: u64 = 3600;
+
+/// Longest accepted history retention, in days
+const MAX_HISTORY_RETENTION_DAYS: u32 = 3650;
+
+/// Accepted range of the secret size limit, in bytes
+const SECRET_SIZE_RANGE: std::ops::RangeInclusive<usize> = 1_000..=10_000_000;
+
+/// Non-secret user preferences
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    /// TTL in seconds for new secrets
+    pub default_ttl: u32,
+    /// Protect new secrets with a passphrase
+    pub require_passphrase: bool,
+    /// Recipient email notified of quick shares and prefilled in the form
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_recipient: Option<String>,
+    /// Seconds before copied links and secrets are cleared, 0 disables
+    pub clipboard_clear_secs: u64,
+    /// Days to keep receipt history, 0 keeps it forever
+    pub history_retention_days: u32,
+    /// Hide the window to the tray instead of quitting when it is closed
+    pub close_to_tray: bool,
+    /// Global hotkey accelerators
+    pub hotkeys: HotkeySettings,
+    /// Largest secret this app creates before splitting or refusing a file,
+    /// in bytes
+    ///
+    /// A local cap, not read from the server; set it at or below the
+    /// instance's own limit.
+    pub local_max_secret_size: usize,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            default_ttl: 3600,
+            require_passphrase: false,
+            default_recipient: None,
+            clipboard_clear_secs: DEFAULT_CLEAR_AFTER_SECS,
+            history_retention_days: 30,
+            close_to_tray: true,
+            hotkeys: HotkeySettings::default(),
+            local_max_secret_size: 100_000,
+        }
+    }
+}
+
+impl AppSettings {
+    /// Validate settings before saving
+    pub fn validate(&self) -> AppResult<()> {
+        if self.default_ttl == 0 || self.default_ttl > 604800 {
+            return Err(AppError::InvalidInput(
+                "Default TTL must be between 1 second and 7 days (604800 seconds)".to_string(),
+            ));
+        }
+
+        if let Some(recipient) = &self.default_recipient {
+            if !recipient.contains('@') {
+                return Err(AppError::InvalidInput(
+                    "Default recipient must be a valid email address".to_string(),
+                ));
+            }
+        }
+
+        if self.clipboard_clear_secs > MAX_CLIPBOARD_CLEAR_SECS {
+            return Err(AppError::InvalidInput(format!(
+                "Clipboard clear timeout cannot exceed {} seconds",
+                MAX_CLIPBOARD_CLEAR_SECS
+            )));
+        }
+
+        if self.history_retention_days > MAX_HISTORY_RETENTION_DAYS {
+            return Err(AppError::InvalidInput(format!(
+                "History retention cannot exceed {} days",
+                MAX_HISTORY_RETENTION_DAYS
+            )));
+        }
+
+        if !SECRET_SIZE_RANGE.contains(&self.local_max_secret_size) {
+            return Err(AppError::InvalidInput(format!(
+                "Secret size limit must be between {} and {} bytes",
+                SECRET_SIZE_RANGE.start(),
+                SECRET_SIZE_RANGE.end()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+
+/// Settings file with its schema version alongside the fields
+#[derive(Serialize)]
+struct StoredSettings<'a> {
+    version: u32,
+    #[serde(flatten)]
+    settings: &'a AppSettings,
+}
+
+/// Schema version of a stored settings document, 1 if it predates versioning
+fn stored_version(value: &Value) -> u64 {
+    value.get("version").and_then(Value::as_u64).unwrap_or(1)
+}
+
+/// Upgrade a stored settings document to the current schema
+///
+/// Files without a `version` field predate versioning and count as v1.
+/// Versions so far only added settings, which take their defaults; files
+/// from a newer build are read as-is and unknown fields are ignored.
+fn migrate(mut value: Value) -> AppResult<AppSettings> {
+    let version = stored_version(&value);
+    let object = value
+        .as_object_mut()
+        .ok_or_else(|| AppError::Storage("Settings file is not a JSON object".to_string()))?;
+    object.remove("version");
+
+    if version > u64::from(SETTINGS_VERSION) {
+        log::warn!(
+            "Settings file is from a newer app version (v{}); its new settings are ignored \
+             and the file is left as is",
+            version
+        );
+    }
+
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Settings persisted as JSON in the app config directory
+pub struct SettingsStore {
+    path: PathBuf,
+    current: RwLock<AppSettings>,
+    /// The file was written by a newer version and is kept as is
+    read_only: bool,
+}
+
+impl SettingsStore {
+    /// Load settings from disk, migrating older files and falling back to defaults
+    pub fn load(path: PathBuf) -> Self {
+        let (settings, read_only) = match Self::read(&path) {
+            Ok(settings) => settings,
+            Err(e) => {
+                log::warn!("Failed to load settings, using defaults: {}", e);
+                (AppSettings::default(), false)
+            }
+        };
+
+        Self {
+            path,
+            current: RwLock::new(settings),
+            read_only,
+        }
+    }
+
+    /// Read the settings file, also telling whether it is from a newer version
+    fn read(path: &std::path::Path) -> AppResult<(AppSettings, bool)> {
+        let Some(value) = store::read_json::<Value>(path)? else {
+            return Ok((AppSettings::default(), false));
+        };
+
+        let version = stored_version(&value);
+        let settings = migrate(value)?;
+
+        // Write the upgraded file back so the migration runs once; files from
+        // a newer version are never rewritten, which would drop their fields
+        if version < u64::from(SETTINGS_VERSION) {
+            Self::write(path, &settings)?;
+        }
+
+        Ok((settings, version > u64::from(SETTINGS_VERSION)))
+    }
+
+    fn write(path: &std::path::Path, settings: &AppSettings) -> AppResult<()> {
+        store::write_json(
+            path,
+            &StoredSettings {
+                version: SETTINGS_VERSION,
+                settings,
+            },
+        )
+    }
+
+    pub fn get(&self) -> AppSettings {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Apply a change, validate it and persist the result
+    pub fn update<F: FnOnce(&mut AppSettings)>(&self, change: F) -> AppResult<AppSettings> {
+        if self.read_only {
+            return Err(AppError::InvalidInput(
+                "Settings were saved by a newer version of the app, update it to change them"
+                    .to_string(),
+            ));
+        }
+
+        let mut current = self.current.write().unwrap();
+        let mut updated = current.clone();
+        change(&mut updated);
+        updated.validate()?;
+
+        Self::write(&self.path, &updated)?;
+        *current = updated.clone();
+        Ok(updated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_settings() {
+        assert!(AppSettings::default().validate().is_ok());
+
+        let invalid = [
+            AppSettings {
+                default_ttl: 0,
+                ..Default::default()
+            },
+            AppSettings {
+                default_recipient: Some("nobody".to_string()),
+                ..Default::default()
+            },
+            AppSettings {
+                clipboard_clear_secs: MAX_CLIPBOARD_CLEAR_SECS + 1,
+                ..Default::default()
+            },
+            AppSettings {
+                history_retention_days: MAX_HISTORY_RETENTION_DAYS + 1,
+                ..Default::default()
+            },
+            AppSettings {
+                local_max_secret_size: 10,
+                ..Default::default()
+            },
+        ];
+        for settings in invalid {
+            assert!(settings.validate().is_err(), "{:?}", settings);
+        }
+    }
+
+    #[test]
+    fn test_migrate_v1() {
+        let v1 = serde_json::json!({
+            "default_ttl": 300,
+            "close_to_tray": false,
+            "require_passphrase": true,
+            "hotkeys": { "quick_share": "CmdOrCtrl+Shift+S" }
+        });
+
+        let settings = migrate(v1).unwrap();
+        assert_eq!(settings.default_ttl, 300);
+        assert!(!settings.close_to_tray);
+        assert!(settings.require_passphrase);
+        assert_eq!(settings.hotkeys.quick_share.as_deref(), Some("CmdOrCtrl+Shift+S"));
+        assert_eq!(settings.clipboard_clear_secs, DEFAULT_CLEAR_AFTER_SECS);
+    }
+
+    #[test]
+    fn test_load_upgrades_file() {
+        let path = std::env::temp_dir().join(format!("ots-settings-{}.json", std::process::id()));
+        std::fs::write(&path, r#"{"default_ttl": 60}"#).unwrap();
+
+        let store = SettingsStore::load(path.clone());
+        assert_eq!(store.get().default_ttl, 60);
+
+        let written: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["version"], SETTINGS_VERSION);
+        assert_eq!(written["default_ttl"], 60);
+
+        assert!(store.update(|s| s.default_ttl = 0).is_err());
+        assert_eq!(store.get().default_ttl, 60);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_load_keeps_newer_file() {
+        let path =
+            std::env::temp_dir().join(format!("ots-settings-newer-{}.json", std::process::id()));
+        let newer = format!(
+            r#"{{"version": {}, "default_ttl": 60, "added_later": true}}"#,
+            SETTINGS_VERSION + 1
+        );
+        std::fs::write(&path, &newer).unwrap();
+
+        let store = SettingsStore::load(path.clone());
+        assert_eq!(store.get().default_ttl, 60);
+        assert!(store.update(|s| s.default_ttl = 120).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), newer);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
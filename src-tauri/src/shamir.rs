@@ -0,0 +1,258 @@
+use crate::error::{AppError, AppResult};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64, Engine};
+use blahaj::{Share, Sharks};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// Marks a secret as a Shamir share, e.g. `ots-share:1:<set>:2-of-3:<data>`
+const SHARE_PREFIX: &str = "ots-share";
+
+/// Share format written by this build
+const SHARE_VERSION: u32 = 1;
+
+/// Most shares a secret may be split into, each becomes its own secret
+pub const MAX_SHARES: u8 = 16;
+
+/// Bytes of the secret's SHA-256 split along with it, so that shares from
+/// different secrets or a damaged share are noticed when combining
+const CHECKSUM_LENGTH: usize = 4;
+
+/// Request to split a secret into M-of-N shares, one OTS secret each
+#[derive(Debug, Deserialize)]
+pub struct SplitSecretRequest {
+    pub secret: String,
+    /// Shares needed to reconstruct the secret (M)
+    pub threshold: u8,
+    /// Shares created (N)
+    pub shares: u8,
+    #[serde(default)]
+    pub passphrase: Option<String>,
+    pub ttl: u32,
+    /// Recipient of each share by position, empty entries notify nobody
+    #[serde(default)]
+    pub recipients: Vec<String>,
+}
+
+/// One share of a split secret, as parsed from its text form
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ShareText {
+    version: u32,
+    set: String,
+    threshold: u8,
+    total: u8,
+    data: Vec<u8>,
+}
+
+impl ShareText {
+    fn encode(&self) -> String {
+        format!(
+            "{}:{}:{}:{}-of-{}:{}",
+            SHARE_PREFIX,
+            self.version,
+            self.set,
+            self.threshold,
+            self.total,
+            BASE64.encode(&self.data)
+        )
+    }
+
+    fn parse(text: &str) -> AppResult<Self> {
+        let invalid = || AppError::InvalidInput("Not a valid share".to_string());
+
+        let parts: Vec<&str> = text.trim().split(':').collect();
+        let [SHARE_PREFIX, version, set, counts, data] = parts.as_slice() else {
+            return Err(invalid());
+        };
+
+        let version = version.parse::<u32>().map_err(|_| invalid())?;
+        if version > SHARE_VERSION {
+            return Err(AppError::InvalidInput(format!(
+                "Share was created by a newer version (v{}), please update the app",
+                version
+            )));
+        }
+
+        let (threshold, total) = counts.split_once("-of-").ok_or_else(invalid)?;
+        let share = Self {
+            version,
+            set: set.to_string(),
+            threshold: threshold.parse().map_err(|_| invalid())?,
+            total: total.parse().map_err(|_| invalid())?,
+            data: BASE64.decode(data).map_err(|_| invalid())?,
+        };
+
+        if share.data.len() < 2 || share.threshold < 2 || share.threshold > share.total {
+            return Err(invalid());
+        }
+        Ok(share)
+    }
+}
+
+/// Whether a retrieved secret holds a share rather than ordinary text
+pub fn is_share(text: &str) -> bool {
+    text.trim_start()
+        .strip_prefix(SHARE_PREFIX)
+        .is_some_and(|rest| rest.starts_with(':'))
+}
+
+/// Split a secret into `total` shares, any `threshold` of which rebuild it
+///
+/// Returns the random ID of the share set and the shares as text.
+pub fn split(secret: &str, threshold: u8, total: u8) -> AppResult<(String, Vec<String>)> {
+    validate_counts(threshold, total)?;
+    if secret.is_empty() {
+        return Err(AppError::InvalidInput("Secret cannot be empty".to_string()));
+    }
+
+    let mut payload = secret.as_bytes().to_vec();
+    payload.extend_from_slice(&checksum(secret.as_bytes()));
+
+    let set = format!("{:016x}", rand::random::<u64>());
+    let shares = Sharks(threshold)
+        .dealer(&payload)
+        .take(total.into())
+        .map(|share| {
+            ShareText {
+                version: SHARE_VERSION,
+                set: set.clone(),
+                threshold,
+                total,
+                data: Vec::from(&share),
+            }
+            .encode()
+        })
+        .collect();
+
+    Ok((set, shares))
+}
+
+/// Check that `count` inputs can be enough for the set `share` belongs to,
+/// before any linked share is retrieved and burned
+pub fn check_count(share: &str, count: usize) -> AppResult<()> {
+    let share = ShareText::parse(share)?;
+    if count < share.threshold.into() {
+        return Err(too_few(&share, count));
+    }
+
+    Ok(())
+}
+
+/// Rebuild a secret from at least `threshold` shares of the same set
+pub fn combine(shares: &[String]) -> AppResult<String> {
+    let parsed = shares
+        .iter()
+        .map(|text| ShareText::parse(text))
+        .collect::<AppResult<Vec<_>>>()?;
+
+    let first = parsed
+        .first()
+        .ok_or_else(|| AppError::InvalidInput("No shares given".to_string()))?;
+    if parsed
+        .iter()
+        .any(|share| share.set != first.set || share.threshold != first.threshold)
+    {
+        return Err(AppError::InvalidInput(
+            "Shares belong to different secrets".to_string(),
+        ));
+    }
+
+    // The same share entered twice would break the interpolation
+    let mut distinct: Vec<Share> = Vec::new();
+    for share in &parsed {
+        let share = Share::try_from(share.data.as_slice())
+            .map_err(|e| AppError::InvalidInput(e.to_string()))?;
+        if !distinct.iter().any(|known| known.x == share.x) {
+            distinct.push(share);
+        }
+    }
+
+    if distinct.len() < first.threshold.into() {
+        return Err(too_few(first, distinct.len()));
+    }
+
+    let payload = Sharks(first.threshold)
+        .recover(&distinct)
+        .map_err(|e| AppError::InvalidInput(e.to_string()))?;
+
+    let damaged = || AppError::InvalidInput("Shares do not rebuild a valid secret".to_string());
+    let split_at = payload
+        .len()
+        .checked_sub(CHECKSUM_LENGTH)
+        .ok_or_else(damaged)?;
+    let (secret, sum) = payload.split_at(split_at);
+    if checksum(secret) != sum {
+        return Err(damaged());
+    }
+
+    String::from_utf8(secret.to_vec()).map_err(|_| damaged())
+}
+
+fn too_few(share: &ShareText, count: usize) -> AppError {
+    AppError::InvalidInput(format!(
+        "{} of {} shares are needed, got {}",
+        share.threshold, share.total, count
+    ))
+}
+
+fn validate_counts(threshold: u8, total: u8) -> AppResult<()> {
+    if threshold < 2 || threshold > total || total > MAX_SHARES {
+        return Err(AppError::InvalidInput(format!(
+            "Shares must satisfy 2 <= needed <= total <= {}",
+            MAX_SHARES
+        )));
+    }
+
+    Ok(())
+}
+
+fn checksum(contents: &[u8]) -> [u8; CHECKSUM_LENGTH] {
+    let digest = Sha256::digest(contents);
+    let mut sum = [0; CHECKSUM_LENGTH];
+    sum.copy_from_slice(&digest[..CHECKSUM_LENGTH]);
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_and_combine() {
+        let (set, shares) = split("root:hunter2", 2, 3).unwrap();
+        assert_eq!(shares.len(), 3);
+        assert!(shares[0].starts_with("ots-share:1:"));
+        assert!(shares.iter().all(|share| is_share(share)));
+        assert!(shares[0].contains(&set));
+
+        assert_eq!(combine(&shares[1..]).unwrap(), "root:hunter2");
+        assert_eq!(
+            combine(&[shares[2].clone(), shares[0].clone()]).unwrap(),
+            "root:hunter2"
+        );
+
+        assert!(check_count(&shares[0], 2).is_ok());
+        assert!(check_count(&shares[0], 1).is_err());
+
+        // One share, even if entered twice, is not enough
+        assert!(combine(&[shares[0].clone(), shares[0].clone()]).is_err());
+
+        let (_, others) = split("root:hunter2", 2, 3).unwrap();
+        assert!(combine(&[shares[0].clone(), others[1].clone()]).is_err());
+    }
+
+    #[test]
+    fn test_rejects_damaged_shares() {
+        let (_, shares) = split("root:hunter2", 2, 2).unwrap();
+
+        let mut share = ShareText::parse(&shares[1]).unwrap();
+        share.data[3] ^= 0xff;
+        assert!(combine(&[shares[0].clone(), share.encode()]).is_err());
+
+        assert!(!is_share("ots-shared secret"));
+        assert!(combine(&["ots-share:1:abc:2-of-3".to_string()]).is_err());
+        assert!(combine(&["ots-share:2:abc:2-of-3:AAAA".to_string()]).is_err());
+        assert!(split("secret", 1, 3).is_err());
+        assert!(split("secret", 4, 3).is_err());
+        assert!(split("secret", 2, MAX_SHARES + 1).is_err());
+    }
+}
@@ -0,0 +1,94 @@
+use crate::error::AppResult;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Read a JSON file, returning `None` if it doesn't exist yet
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> AppResult<Option<T>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(Some(serde_json::from_str(&contents)?))
+}
+
+/// Write a JSON file atomically, readable only by the current user
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> AppResult<()> {
+    let json = serde_json::to_vec_pretty(value)?;
+    write_private(path, &json)
+}
+
+/// Write a file atomically, readable only by the current user
+///
+/// The contents go to a uniquely named temporary file next to the target,
+/// which is renamed over it, so a crash never leaves a half-written file
+/// behind and no other file in the directory is touched. Temporary files
+/// are created readable only by their owner.
+pub fn write_private(path: &Path, contents: &[u8]) -> AppResult<()> {
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    fs::create_dir_all(parent)?;
+
+    let mut file = tempfile::NamedTempFile::new_in(parent)?;
+    file.write_all(contents)?;
+    file.as_file().sync_all()?;
+
+    file.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// Move an unreadable file to `<name>.bak`, replacing an older backup
+///
+/// Returns the path of the backup.
+pub fn set_aside(path: &Path) -> AppResult<PathBuf> {
+    let mut backup = path.as_os_str().to_os_string();
+    backup.push(".bak");
+    let backup = PathBuf::from(backup);
+
+    fs::rename(path, &backup)?;
+    Ok(backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_json_round_trip() {
+        let dir = std::env::temp_dir().join(format!("ots-store-{}", std::process::id()));
+        let path = dir.join("values.json");
+
+        assert!(read_json::<BTreeMap<String, u32>>(&path).unwrap().is_none());
+
+        let mut values = BTreeMap::new();
+        values.insert("ttl".to_string(), 3600);
+        write_json(&path, &values).unwrap();
+
+        let loaded: BTreeMap<String, u32> = read_json(&path).unwrap().unwrap();
+        assert_eq!(loaded, values);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_keeps_sibling_tmp_file() {
+        let dir = std::env::temp_dir().join(format!("ots-store-tmp-{}", std::process::id()));
+        let path = dir.join("report");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("report.tmp"), "draft").unwrap();
+
+        write_private(&path, b"final").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "final");
+        assert_eq!(fs::read_to_string(dir.join("report.tmp")).unwrap(), "draft");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
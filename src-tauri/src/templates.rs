@@ -0,0 +1,486 @@
+use crate::api::CreateSecretRequest;
+use crate::error::{AppError, AppResult};
+use crate::passphrase;
+use crate::store;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// File name of the secret templates in the app config directory
+pub const TEMPLATES_FILE: &str = "templates.json";
+
+/// First line of a templated secret, followed by the template ID and name
+const TEMPLATE_PREFIX: &str = "ots-template:";
+
+/// Indent of the lines of a multi-line value
+const CONTINUATION: &str = "  ";
+
+/// Whether secrets from a template need a passphrase
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PassphraseRule {
+    #[default]
+    Optional,
+    /// Refuse to create the secret without a passphrase
+    Required,
+    /// Generate a passphrase when none is given
+    Generate,
+}
+
+/// A named field of a template
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateField {
+    /// Shown in the form and in the secret, also the key of the value
+    pub label: String,
+    /// Allow several lines, e.g. for a VPN config
+    #[serde(default)]
+    pub multiline: bool,
+    /// Allow the field to be left empty
+    #[serde(default)]
+    pub optional: bool,
+}
+
+/// A recurring format of secret, e.g. database credentials
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecretTemplate {
+    pub id: String,
+    pub name: String,
+    pub fields: Vec<TemplateField>,
+    /// TTL the form starts with, in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_ttl: Option<u32>,
+    #[serde(default)]
+    pub passphrase: PassphraseRule,
+}
+
+impl SecretTemplate {
+    fn validate(&self) -> AppResult<()> {
+        let id_chars = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-';
+        if self.id.is_empty() || !self.id.chars().all(id_chars) {
+            return Err(AppError::InvalidInput(
+                "Template ID must be lowercase letters, digits and dashes".to_string(),
+            ));
+        }
+        if self.name.trim().is_empty() || self.name.contains('\n') {
+            return Err(AppError::InvalidInput(
+                "Template name must be one non-empty line".to_string(),
+            ));
+        }
+        if self.fields.is_empty() {
+            return Err(AppError::InvalidInput(
+                "Template needs at least one field".to_string(),
+            ));
+        }
+
+        for (i, field) in self.fields.iter().enumerate() {
+            let label = field.label.trim();
+            if label.is_empty() || label != field.label || label.contains([':', '\n']) {
+                return Err(AppError::InvalidInput(format!(
+                    "Invalid field label \"{}\": labels are one line without colons",
+                    field.label
+                )));
+            }
+            if self.fields[..i].iter().any(|f| f.label == field.label) {
+                return Err(AppError::InvalidInput(format!(
+                    "Duplicate field label \"{}\"",
+                    field.label
+                )));
+            }
+        }
+
+        if self.default_ttl.is_some_and(|ttl| ttl == 0 || ttl > 604800) {
+            return Err(AppError::InvalidInput(
+                "Default TTL must be between 1 second and 7 days (604800 seconds)".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Render field values, keyed by label, into a secret body
+    ///
+    /// Values are kept byte for byte, so `parse` returns them unchanged,
+    /// including surrounding whitespace and blank lines.
+    pub fn render(&self, values: &HashMap<String, String>) -> AppResult<String> {
+        if let Some(unknown) = values
+            .keys()
+            .find(|key| !self.fields.iter().any(|f| &f.label == *key))
+        {
+            return Err(AppError::InvalidInput(format!(
+                "Template {} has no field \"{}\"",
+                self.id, unknown
+            )));
+        }
+
+        let mut body = format!("{}{} {}", TEMPLATE_PREFIX, self.id, self.name);
+        for field in &self.fields {
+            let value = values
+                .get(&field.label)
+                .map(String::as_str)
+                .unwrap_or_default();
+            if value.trim().is_empty() {
+                if field.optional {
+                    continue;
+                }
+                return Err(AppError::InvalidInput(format!(
+                    "{} is required",
+                    field.label
+                )));
+            }
+
+            if !value.contains('\n') {
+                body.push_str(&format!("\n{}: {}", field.label, value));
+            } else if field.multiline {
+                body.push_str(&format!("\n{}:", field.label));
+                for line in value.split('\n') {
+                    body.push_str(&format!("\n{}{}", CONTINUATION, line));
+                }
+            } else {
+                return Err(AppError::InvalidInput(format!(
+                    "{} must be a single line",
+                    field.label
+                )));
+            }
+        }
+
+        Ok(body)
+    }
+}
+
+/// Which template a secret should be rendered from, and its field values
+#[derive(Debug, Deserialize)]
+pub struct TemplateInput {
+    pub id: String,
+    /// Values keyed by field label
+    pub fields: HashMap<String, String>,
+}
+
+/// A retrieved secret rendered from a template
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TemplatedSecret {
+    /// ID of the sender's template, which may not exist locally
+    pub template: String,
+    pub name: String,
+    pub fields: Vec<LabeledField>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LabeledField {
+    pub label: String,
+    pub value: String,
+}
+
+/// Parse a secret rendered from a template back into labeled fields
+/// Returns `None` for other secrets, which are shown as text instead
+pub fn parse(secret: &str) -> Option<TemplatedSecret> {
+    // Split on '\n' alone, as `lines` would drop a '\r' ending a value
+    let mut lines = secret.split('\n');
+    let (template, name) = lines
+        .next()?
+        .strip_prefix(TEMPLATE_PREFIX)?
+        .split_once(' ')?;
+
+    let mut fields: Vec<LabeledField> = Vec::new();
+    // Whether the last field already has a continuation line
+    let mut continued = false;
+    for line in lines {
+        if let Some(rest) = line.strip_prefix(CONTINUATION) {
+            let field = fields.last_mut()?;
+            if continued {
+                field.value.push('\n');
+            }
+            field.value.push_str(rest);
+            continued = true;
+        } else {
+            let (label, value) = line.split_once(':')?;
+            fields.push(LabeledField {
+                label: label.to_string(),
+                value: value.strip_prefix(' ').unwrap_or(value).to_string(),
+            });
+            continued = false;
+        }
+    }
+
+    if template.is_empty() || fields.is_empty() {
+        return None;
+    }
+    Some(TemplatedSecret {
+        template: template.to_string(),
+        name: name.to_string(),
+        fields,
+    })
+}
+
+/// Templates that come with the app, until the user changes the list
+fn builtin() -> Vec<SecretTemplate> {
+    let field = |label: &str| TemplateField {
+        label: label.to_string(),
+        multiline: false,
+        optional: false,
+    };
+
+    vec![
+        SecretTemplate {
+            id: "database".to_string(),
+            name: "Database credentials".to_string(),
+            fields: vec![
+                field("Host"),
+                field("Port"),
+                field("User"),
+                field("Password"),
+                TemplateField {
+                    optional: true,
+                    ..field("Database")
+                },
+            ],
+            default_ttl: Some(86400),
+            passphrase: PassphraseRule::Optional,
+        },
+        SecretTemplate {
+            id: "wifi".to_string(),
+            name: "Wi-Fi network".to_string(),
+            fields: vec![
+                field("Network"),
+                field("Password"),
+                TemplateField {
+                    optional: true,
+                    ..field("Security")
+                },
+            ],
+            default_ttl: Some(604800),
+            passphrase: PassphraseRule::Optional,
+        },
+        SecretTemplate {
+            id: "vpn".to_string(),
+            name: "VPN configuration".to_string(),
+            fields: vec![
+                field("Server"),
+                field("User"),
+                TemplateField {
+                    optional: true,
+                    ..field("Password")
+                },
+                TemplateField {
+                    multiline: true,
+                    ..field("Config")
+                },
+            ],
+            default_ttl: Some(86400),
+            passphrase: PassphraseRule::Generate,
+        },
+    ]
+}
+
+/// Secret templates persisted as JSON
+pub struct Templates {
+    path: PathBuf,
+    templates: Mutex<Vec<SecretTemplate>>,
+}
+
+impl Templates {
+    /// Load the templates, starting with the built-in ones if there are none yet
+    pub fn load(path: PathBuf) -> Self {
+        let templates = match store::read_json(&path) {
+            Ok(templates) => templates.unwrap_or_else(builtin),
+            Err(e) => {
+                log::warn!("Failed to load templates, using built-in ones: {}", e);
+                builtin()
+            }
+        };
+
+        Self {
+            path,
+            templates: Mutex::new(templates),
+        }
+    }
+
+    pub fn list(&self) -> Vec<SecretTemplate> {
+        self.templates.lock().unwrap().clone()
+    }
+
+    pub fn get(&self, id: &str) -> AppResult<SecretTemplate> {
+        let templates = self.templates.lock().unwrap();
+        templates
+            .iter()
+            .find(|t| t.id == id)
+            .cloned()
+            .ok_or_else(|| AppError::InvalidInput(format!("Unknown template: {}", id)))
+    }
+
+    /// Add a template or replace the one with the same ID
+    pub fn save(&self, template: SecretTemplate) -> AppResult<()> {
+        template.validate()?;
+
+        let mut templates = self.templates.lock().unwrap();
+        match templates.iter_mut().find(|t| t.id == template.id) {
+            Some(existing) => *existing = template,
+            None => templates.push(template),
+        }
+        store::write_json(&self.path, &*templates)
+    }
+
+    pub fn delete(&self, id: &str) -> AppResult<()> {
+        let mut templates = self.templates.lock().unwrap();
+        let before = templates.len();
+        templates.retain(|t| t.id != id);
+
+        if templates.len() == before {
+            return Err(AppError::InvalidInput(format!("Unknown template: {}", id)));
+        }
+        store::write_json(&self.path, &*templates)
+    }
+
+    /// Render the secret of `request` from a template and apply its
+    /// passphrase rule, returning the passphrase if one was generated
+    pub fn apply(
+        &self,
+        input: &TemplateInput,
+        mut request: CreateSecretRequest,
+    ) -> AppResult<(CreateSecretRequest, Option<String>)> {
+        let template = self.get(&input.id)?;
+        request.secret = template.render(&input.fields)?;
+
+        let mut generated = None;
+        if request.passphrase.as_deref().unwrap_or_default().is_empty() {
+            match template.passphrase {
+                PassphraseRule::Optional => request.passphrase = None,
+                PassphraseRule::Required => {
+                    return Err(AppError::InvalidInput(format!(
+                        "Secrets from the {} template need a passphrase",
+                        template.name
+                    )))
+                }
+                PassphraseRule::Generate => {
+                    generated = Some(passphrase::generate());
+                    request.passphrase = generated.clone();
+                }
+            }
+        }
+
+        Ok((request, generated))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_and_parse() {
+        let vpn = builtin().into_iter().find(|t| t.id == "vpn").unwrap();
+        let body = vpn
+            .render(&values(&[
+                ("Server", "vpn.example.com"),
+                ("User", "alice"),
+                ("Config", "[Interface]\n\nAddress = 10.0.0.2/32\n"),
+            ]))
+            .unwrap();
+
+        let parsed = parse(&body).unwrap();
+        assert_eq!(parsed.template, "vpn");
+        assert_eq!(parsed.name, "VPN configuration");
+        assert_eq!(parsed.fields.len(), 3);
+        assert_eq!(parsed.fields[0].value, "vpn.example.com");
+        assert_eq!(parsed.fields[2].label, "Config");
+        assert_eq!(
+            parsed.fields[2].value,
+            "[Interface]\n\nAddress = 10.0.0.2/32\n"
+        );
+
+        assert!(vpn.render(&values(&[("Server", "s")])).is_err());
+        assert!(vpn
+            .render(&values(&[
+                ("Server", "s"),
+                ("User", "a\nb"),
+                ("Config", "c")
+            ]))
+            .is_err());
+        assert!(vpn
+            .render(&values(&[
+                ("Server", "s"),
+                ("User", "u"),
+                ("Config", "c"),
+                ("Port", "1")
+            ]))
+            .is_err());
+
+        assert!(vpn
+            .render(&values(&[
+                ("Server", "s"),
+                ("User", " \n "),
+                ("Config", "c")
+            ]))
+            .is_err());
+
+        assert_eq!(parse("just a password"), None);
+        assert_eq!(parse("ots-template:x Name\nno colon here"), None);
+    }
+
+    #[test]
+    fn test_values_round_trip_exactly() {
+        let database = builtin().into_iter().find(|t| t.id == "database").unwrap();
+        let body = database
+            .render(&values(&[
+                ("Host", " db.example.com"),
+                ("Port", "5432"),
+                ("User", "app"),
+                ("Password", "pass "),
+            ]))
+            .unwrap();
+        let parsed = parse(&body).unwrap();
+        assert_eq!(parsed.fields[0].value, " db.example.com");
+        assert_eq!(parsed.fields[3].value, "pass ");
+
+        let vpn = builtin().into_iter().find(|t| t.id == "vpn").unwrap();
+        let config = "\n\n[Interface]\r\nAddress = 10.0.0.2/32  \n\n";
+        let body = vpn
+            .render(&values(&[
+                ("Server", "s"),
+                ("User", "u"),
+                ("Config", config),
+            ]))
+            .unwrap();
+        assert_eq!(parse(&body).unwrap().fields[2].value, config);
+    }
+
+    #[test]
+    fn test_apply_passphrase_rules() {
+        let path = std::env::temp_dir().join(format!("ots-templates-{}.json", std::process::id()));
+        let templates = Templates::load(path.clone());
+        let request = CreateSecretRequest {
+            secret: String::new(),
+            passphrase: Some(String::new()),
+            ttl: 3600,
+            recipient: None,
+        };
+
+        let wifi = TemplateInput {
+            id: "wifi".to_string(),
+            fields: values(&[("Network", "office"), ("Password", "hunter2")]),
+        };
+        let (created, generated) = templates.apply(&wifi, request.clone()).unwrap();
+        assert!(created.secret.starts_with("ots-template:wifi "));
+        assert_eq!((created.passphrase, generated), (None, None));
+
+        let mut strict = templates.get("wifi").unwrap();
+        strict.passphrase = PassphraseRule::Required;
+        templates.save(strict).unwrap();
+        assert!(templates.apply(&wifi, request.clone()).is_err());
+
+        let mut invalid = templates.get("wifi").unwrap();
+        invalid.id = "Wi Fi".to_string();
+        assert!(templates.save(invalid).is_err());
+
+        templates.delete("wifi").unwrap();
+        assert!(Templates::load(path.clone()).get("wifi").is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
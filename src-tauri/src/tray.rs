@@ -0,0 +1,268 @@
+use crate::api::OtsClient;
+use crate::error::{AppError, AppResult};
+use crate::history::History;
+use crate::quick_share;
+use crate::resolve;
+use crate::settings::SettingsStore;
+use crate::storage::SecureStorage;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::menu::{CheckMenuItemBuilder, Menu, MenuBuilder, MenuItemBuilder, SubmenuBuilder};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Emitter, Manager};
+
+const TRAY_ID: &str = "main";
+
+/// Number of history entries listed in the tray menu
+const RECENT_ENTRIES: usize = 5;
+
+/// How often the connection status is re-checked
+const STATUS_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Event emitted when a history entry is picked from the tray
+pub const OPEN_HISTORY_EVENT: &str = "open-history-entry";
+
+/// Event emitted after the active profile changed
+pub const PROFILE_CHANGED_EVENT: &str = "profile-changed";
+
+const QUICK_SHARE_ID: &str = "quick_share";
+const STATUS_ID: &str = "status";
+const CLOSE_TO_TRAY_ID: &str = "close_to_tray";
+const SHOW_ID: &str = "show";
+const QUIT_ID: &str = "quit";
+const HISTORY_PREFIX: &str = "history:";
+const PROFILE_PREFIX: &str = "profile:";
+
+/// Result of the last connection test
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionStatus {
+    Unknown,
+    NotConfigured,
+    Connected,
+    Failed,
+}
+
+impl ConnectionStatus {
+    fn label(self) -> &'static str {
+        match self {
+            ConnectionStatus::Unknown => "Status: Checking...",
+            ConnectionStatus::NotConfigured => "Status: Not configured",
+            ConnectionStatus::Connected => "Status: Connected",
+            ConnectionStatus::Failed => "Status: Connection failed",
+        }
+    }
+}
+
+/// Tray state shared between menu rebuilds
+pub struct TrayState {
+    status: Mutex<ConnectionStatus>,
+}
+
+impl Default for TrayState {
+    fn default() -> Self {
+        Self {
+            status: Mutex::new(ConnectionStatus::Unknown),
+        }
+    }
+}
+
+/// Create the tray icon and start checking the connection status
+pub fn init(app: &AppHandle) -> AppResult<()> {
+    let menu = build_menu(app)?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Onetimesecret Desktop")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| handle_menu_event(app, event.id().as_ref()))
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                crate::focus_main_window(tray.app_handle());
+            }
+        });
+
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+
+    builder.build(app).map_err(tauri_error)?;
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            refresh_status(&handle).await;
+            tokio::time::sleep(STATUS_INTERVAL).await;
+        }
+    });
+
+    Ok(())
+}
+
+/// Rebuild the tray menu after history, profiles or status changed
+pub fn refresh_menu(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+
+    match build_menu(app) {
+        Ok(menu) => {
+            if let Err(e) = tray.set_menu(Some(menu)) {
+                log::warn!("Failed to update tray menu: {}", e);
+            }
+        }
+        Err(e) => log::warn!("Failed to build tray menu: {}", e),
+    }
+}
+
+/// Re-test the connection of the active profile and update the menu
+pub async fn refresh_status(app: &AppHandle) {
+    set_status(app, ConnectionStatus::Unknown);
+
+    let status = match resolve::api_config() {
+        Ok(Some(config)) => match OtsClient::new(config).await {
+            Ok(client) => match client.test_connection().await {
+                Ok(true) => ConnectionStatus::Connected,
+                Ok(false) | Err(_) => ConnectionStatus::Failed,
+            },
+            Err(_) => ConnectionStatus::NotConfigured,
+        },
+        Ok(None) => ConnectionStatus::NotConfigured,
+        Err(e) => {
+            log::warn!("Failed to load configuration for status check: {}", e);
+            ConnectionStatus::Failed
+        }
+    };
+
+    set_status(app, status);
+}
+
+fn set_status(app: &AppHandle, status: ConnectionStatus) {
+    *app.state::<TrayState>().status.lock().unwrap() = status;
+    refresh_menu(app);
+}
+
+fn build_menu(app: &AppHandle) -> AppResult<Menu<tauri::Wry>> {
+    let status = *app.state::<TrayState>().status.lock().unwrap();
+    let settings = app.state::<SettingsStore>().get();
+
+    let share_item = MenuItemBuilder::with_id(QUICK_SHARE_ID, "Share Clipboard as Secret")
+        .build(app)
+        .map_err(tauri_error)?;
+    let status_item = MenuItemBuilder::with_id(STATUS_ID, status.label())
+        .enabled(false)
+        .build(app)
+        .map_err(tauri_error)?;
+
+    let mut recent = SubmenuBuilder::new(app, "Recent Secrets");
+    let entries = app.state::<History>().recent(RECENT_ENTRIES);
+    if entries.is_empty() {
+        let empty = MenuItemBuilder::with_id("history_empty", "No secrets yet")
+            .enabled(false)
+            .build(app)
+            .map_err(tauri_error)?;
+        recent = recent.item(&empty);
+    }
+    for entry in &entries {
+        let label = match &entry.recipient {
+            Some(recipient) => format!("{} ({})", short_key(&entry.metadata_key), recipient),
+            None => short_key(&entry.metadata_key),
+        };
+        recent = recent.text(format!("{}{}", HISTORY_PREFIX, entry.metadata_key), label);
+    }
+    let recent = recent.build().map_err(tauri_error)?;
+
+    let index = SecureStorage::profile_index()?;
+    let mut profiles = SubmenuBuilder::new(app, "Profile");
+    for name in &index.names {
+        let item = CheckMenuItemBuilder::with_id(format!("{}{}", PROFILE_PREFIX, name), name)
+            .checked(*name == index.active)
+            .build(app)
+            .map_err(tauri_error)?;
+        profiles = profiles.item(&item);
+    }
+    let profiles = profiles.build().map_err(tauri_error)?;
+
+    let close_to_tray = CheckMenuItemBuilder::with_id(CLOSE_TO_TRAY_ID, "Close to Tray")
+        .checked(settings.close_to_tray)
+        .build(app)
+        .map_err(tauri_error)?;
+
+    MenuBuilder::new(app)
+        .item(&share_item)
+        .separator()
+        .item(&recent)
+        .item(&profiles)
+        .item(&status_item)
+        .separator()
+        .item(&close_to_tray)
+        .text(SHOW_ID, "Show Window")
+        .text(QUIT_ID, "Quit")
+        .build()
+        .map_err(tauri_error)
+}
+
+fn handle_menu_event(app: &AppHandle, id: &str) {
+    match id {
+        QUICK_SHARE_ID => quick_share::spawn(app),
+        CLOSE_TO_TRAY_ID => {
+            let settings = app.state::<SettingsStore>();
+            if let Err(e) = settings.update(|s| s.close_to_tray = !s.close_to_tray) {
+                log::warn!("Failed to save settings: {}", e);
+            }
+            refresh_menu(app);
+        }
+        SHOW_ID => crate::focus_main_window(app),
+        QUIT_ID => app.exit(0),
+        _ => {
+            if let Some(metadata_key) = id.strip_prefix(HISTORY_PREFIX) {
+                if let Some(entry) = app.state::<History>().get(metadata_key) {
+                    crate::focus_main_window(app);
+                    let _ = app.emit(OPEN_HISTORY_EVENT, entry);
+                }
+            } else if let Some(profile) = id.strip_prefix(PROFILE_PREFIX) {
+                if let Err(e) = switch_profile(app, profile) {
+                    log::warn!("Failed to switch profile: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Activate a profile, tell the frontend and re-check the connection
+pub fn switch_profile(app: &AppHandle, profile: &str) -> AppResult<()> {
+    SecureStorage::set_active_profile(profile)?;
+
+    let _ = app.emit(PROFILE_CHANGED_EVENT, profile);
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        refresh_status(&handle).await;
+    });
+
+    Ok(())
+}
+
+/// First characters of a key, enough to recognize it in a menu
+fn short_key(key: &str) -> String {
+    key.chars().take(8).collect()
+}
+
+fn tauri_error(err: tauri::Error) -> AppError {
+    AppError::Configuration(format!("Tray error: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_key() {
+        assert_eq!(short_key("abcdefghijkl"), "abcdefgh");
+        assert_eq!(short_key("abc"), "abc");
+    }
+}
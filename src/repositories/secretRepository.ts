@@ -1,4 +1,7 @@
 import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+import type { KeyKind } from '@/stores/keyringStore'
+import type { TemplateInput } from '@/stores/templateStore'
 
 export interface CreateSecretRequest {
   secret: string
@@ -13,19 +16,269 @@ export interface CreateSecretResponse {
   metadataKey: string
 }
 
+export interface CreatedSecretResponse extends CreateSecretResponse {
+  /** Passphrase a template rule generated, to be sent separately */
+  generated_passphrase?: string
+}
+
 export interface RetrieveSecretRequest {
   key: string
   passphrase?: string
 }
 
+export interface ReceivedFile {
+  id: string
+  name: string
+  mime_type: string
+  size: number
+}
+
 export interface RetrieveSecretResponse {
   secret: string
   metadata?: {
     createdAt: string
     expiresAt: string
   }
+  /** Set when the secret is a shared file; `secret` is empty then */
+  file?: ReceivedFile
+  /** Number of secrets a split payload was reassembled from */
+  chunks?: number
+  /** Set when the secret was encrypted to one of the user's keys */
+  decrypted_with?: KeyKind
+  /** Why an encrypted secret could not be decrypted; `secret` holds the ciphertext then */
+  decrypt_error?: string
+  /** Labeled fields, if the secret was rendered from a template */
+  template?: TemplatedSecret
+  /** Someone's request for a secret, answered with `answerIncomingRequest` */
+  request?: ReceivedRequest
+  /** ID of the user's own request this secret answered and closed */
+  answered_request?: string
+}
+
+export interface TemplatedSecret {
+  /** ID of the sender's template, which may not exist locally */
+  template: string
+  name: string
+  fields: { label: string; value: string }[]
+}
+
+export interface ChunkedSecretResponse extends CreateSecretResponse {
+  /** Number of secrets holding the payload, 1 when it fit in one */
+  chunks: number
+}
+
+export interface ShareFileRequest {
+  path: string
+  passphrase?: string
+  ttl: number
+  recipient?: string
+}
+
+/** One zip archive of all files, or one secret per file */
+export type ShareMode = 'archive' | 'separate'
+
+export interface ShareFilesRequest {
+  /** Operation ID, to cancel the upload and match its progress events */
+  id?: string
+  paths: string[]
+  mode: ShareMode
+  /** Split files over the secret size limit across several secrets */
+  chunked?: boolean
+  /** Name of a known recipient to encrypt each file to */
+  encrypt_to?: string
+  passphrase?: string
+  ttl: number
+  recipient?: string
+}
+
+export interface SharedFile extends CreateSecretResponse {
+  /** File name, or the archive name when files were combined */
+  name: string
+}
+
+export interface FilePreview {
+  path: string
+  name: string
+  mime_type: string
+  size: number
+  /** Start of the contents for text files */
+  preview?: string
+  /** Whether the file alone exceeds the secret size limit */
+  too_large: boolean
+}
+
+export type ErrorCorrection = 'low' | 'medium' | 'quartile' | 'high'
+
+export interface QrImage {
+  svg: string
+  png: string
+  width: number
+}
+
+export interface HistoryEntry {
+  metadata_key: string
+  link: string
+  profile: string
+  ttl: number
+  recipient?: string
+  created_at: number
+  /** Shared by receipts created together, e.g. the shares of a split secret */
+  group?: string
+  /** Last known state, e.g. `received` or `burned` */
+  state?: string
+  /** Set when the link asks someone to send the user a secret */
+  request?: IncomingRequest
+}
+
+export type RequestStatus = 'open' | 'fulfilled' | 'cancelled'
+
+export interface IncomingRequest {
+  id: string
+  /** The `age1…` recipient the answer is encrypted to */
+  public_key: string
+  note?: string
+  status: RequestStatus
+  closed_at?: number
+}
+
+export interface NewIncomingRequest {
+  /** What is being asked for, shown to the person asked */
+  note?: string
+  /** Seconds the request link stays valid */
+  ttl: number
+  passphrase?: string
+  /** Email address to send the request link to */
+  recipient?: string
+}
+
+/** A request found in a retrieved secret */
+export interface ReceivedRequest {
+  id: string
+  public_key: string
+  note?: string
+}
+
+export interface RequestAnswer {
+  /** The `age1…` recipient from the request */
+  public_key: string
+  secret: string
+  ttl: number
+  passphrase?: string
+  recipient?: string
+}
+
+export type BatchAction = 'burn' | 'refresh'
+
+/** Receipts to act on: by key, a whole group, or all still pending */
+export type BatchSelection =
+  | { select: 'keys'; metadata_keys: string[] }
+  | { select: 'group'; group: string }
+  | { select: 'pending' }
+
+export type BatchRequest = BatchSelection & {
+  /** Operation ID chosen by the caller, to cancel the batch and match its progress events */
+  id: string
+  action: BatchAction
+  concurrency?: number
+}
+
+export interface BatchItem {
+  metadata_key: string
+  state?: string
+  error?: string
+}
+
+/** Progress of a long-running operation, e.g. a batch or file upload */
+export interface OperationProgress<T = unknown> {
+  /** Operation ID given in the request */
+  id: string
+  /** Steps finished so far, including failed ones */
+  done: number
+  total: number
+  /** What the last step produced, e.g. a `BatchItem` or `BulkRowResult` */
+  item?: T
+}
+
+export interface BatchReport {
+  id: string
+  action: BatchAction
+  succeeded: number
+  failed: number
+  /** Whether the batch was stopped before every receipt was processed */
+  cancelled: boolean
+  items: BatchItem[]
+}
+
+export interface SplitSecretRequest {
+  secret: string
+  /** Shares needed to rebuild the secret (M) */
+  threshold: number
+  /** Shares created (N) */
+  shares: number
+  passphrase?: string
+  ttl: number
+  /** Recipient of each share by position; empty entries notify nobody */
+  recipients?: string[]
+}
+
+export interface SplitShare extends CreateSecretResponse {
+  /** Position of the share, from 1 */
+  index: number
+  recipient?: string
 }
 
+export interface SplitSecretResponse {
+  /** ID of the share set, also the history group of the receipts */
+  set: string
+  threshold: number
+  shares: SplitShare[]
+}
+
+export interface CombinedShares {
+  secret?: string
+  /** Why the secret could not be rebuilt after links were retrieved */
+  error?: string
+  /** With `error`, the inputs with retrieved links replaced by their shares, the only copy left */
+  inputs?: string[]
+}
+
+export interface BulkCreateRequest {
+  /** Operation ID, to cancel the batch and match its progress events */
+  id?: string
+  /** CSV with a header line, or a JSON array of rows */
+  input?: string
+  /** File to read the rows from instead of `input` */
+  path?: string
+  /** Secrets created at once, 4 unless set, at most 8 */
+  concurrency?: number
+}
+
+export interface BulkRowResult {
+  /** Position of the row, from 1 */
+  row: number
+  recipient?: string
+  link?: string
+  metadata_key?: string
+  /** Passphrase to hand over separately, if it was generated */
+  generated_passphrase?: string
+  error?: string
+}
+
+export interface BulkReport {
+  /** ID of the batch, also the history group of the receipts */
+  batch: string
+  created: number
+  failed: number
+  /** Whether the batch was stopped before every row was created */
+  cancelled: boolean
+  rows: BulkRowResult[]
+}
+
+export type DeepLinkAction =
+  | { action: 'retrieve'; key: string }
+  | { action: 'metadata'; metadata_key: string }
+  | { action: 'share'; secret?: string; ttl?: number; recipient?: string }
+
 /**
  * Repository for secret operations
  * Follows the repository pattern for clean separation of concerns
@@ -34,14 +287,20 @@ export function useSecretRepository() {
   /**
    * Create a new secret
    * @param request - The secret creation request
+   * @param encryptTo - Name of a known recipient to encrypt the secret to
+   * @param template - Template to render the secret from instead of `request.secret`
    * @returns Response with secret link and keys
    */
   async function createSecret(
-    request: CreateSecretRequest
-  ): Promise<CreateSecretResponse> {
+    request: CreateSecretRequest,
+    encryptTo?: string,
+    template?: TemplateInput
+  ): Promise<CreatedSecretResponse> {
     try {
-      const response = await invoke<CreateSecretResponse>('create_secret', {
-        request
+      const response = await invoke<CreatedSecretResponse>('create_secret', {
+        request,
+        encryptTo,
+        template
       })
       return response
     } catch (error) {
@@ -50,6 +309,28 @@ export function useSecretRepository() {
     }
   }
 
+  /**
+   * Create a secret, splitting it across several linked secrets when it is
+   * larger than the secret size limit in the settings
+   * The returned link opens a manifest that the app reassembles on retrieval
+   * @param request - Secret content, passphrase, TTL
+   * @param encryptTo - Name of a known recipient to encrypt the secret to
+   * @param id - Operation ID, to cancel it with `cancelOperation`
+   * @returns Response with the link to share and the number of chunks
+   */
+  async function createChunkedSecret(
+    request: CreateSecretRequest,
+    encryptTo?: string,
+    id?: string
+  ): Promise<ChunkedSecretResponse> {
+    try {
+      return await invoke<ChunkedSecretResponse>('create_chunked_secret', { request, encryptTo, id })
+    } catch (error) {
+      console.error('Failed to create chunked secret:', error)
+      throw error
+    }
+  }
+
   /**
    * Retrieve a secret by key
    * Note: This burns the secret - it can only be retrieved once
@@ -70,6 +351,136 @@ export function useSecretRepository() {
     }
   }
 
+  /**
+   * Share a file as a secret, wrapped with its name and MIME type
+   * Files larger than the configured secret size limit are refused
+   * @param request - Path of the file and the usual secret options
+   * @returns Response with secret link and keys
+   */
+  async function shareFile(request: ShareFileRequest): Promise<CreateSecretResponse> {
+    try {
+      return await invoke<CreateSecretResponse>('share_file', { request })
+    } catch (error) {
+      console.error('Failed to share file:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Share several files as one archive or as one secret each
+   * @param request - Paths of the files, the share mode and secret options
+   * @returns One entry per created secret
+   */
+  async function shareFiles(request: ShareFilesRequest): Promise<SharedFile[]> {
+    try {
+      return await invoke<SharedFile[]>('share_files', { request })
+    } catch (error) {
+      console.error('Failed to share files:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Describe files for the confirmation step before sharing
+   * @param paths - Paths of the files
+   */
+  async function previewFiles(paths: string[]): Promise<FilePreview[]> {
+    try {
+      return await invoke<FilePreview[]>('preview_files', { paths })
+    } catch (error) {
+      console.error('Failed to preview files:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Write a file from a retrieved secret to disk
+   * @param id - The id from `RetrieveSecretResponse.file`
+   * @param path - Destination chosen by the user
+   */
+  async function saveReceivedFile(id: string, path: string): Promise<void> {
+    try {
+      await invoke('save_received_file', { id, path })
+    } catch (error) {
+      console.error('Failed to save received file:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Drop a retrieved file without saving it
+   * @param id - The id from `RetrieveSecretResponse.file`
+   */
+  async function discardReceivedFile(id: string): Promise<void> {
+    try {
+      await invoke('discard_received_file', { id })
+    } catch (error) {
+      console.error('Failed to discard received file:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Split a secret into M-of-N Shamir shares, one secret per share
+   * @param request - Secret, share counts and one optional recipient per share
+   * @returns The share set and a link per share
+   */
+  async function splitSecret(request: SplitSecretRequest): Promise<SplitSecretResponse> {
+    try {
+      return await invoke<SplitSecretResponse>('split_secret', { request })
+    } catch (error) {
+      console.error('Failed to split secret:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Rebuild a secret from shares pasted as text or given as secret links
+   * Links are retrieved, and so burned, only once every input is recognized
+   * and there are enough of them
+   * @param inputs - Shares or links to secrets holding shares
+   * @param passphrase - Passphrase of the linked secrets, if any
+   * @returns The original secret, or the error and the inputs with retrieved
+   * links replaced by their shares
+   */
+  async function combineShares(inputs: string[], passphrase?: string): Promise<CombinedShares> {
+    try {
+      return await invoke<CombinedShares>('combine_shares', { inputs, passphrase })
+    } catch (error) {
+      console.error('Failed to combine shares:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Create one secret per CSV or JSON row
+   * Rows have `secret` and optionally `recipient`, `ttl` and `passphrase`,
+   * where a passphrase of `generate` creates a random one
+   * @param request - The rows as text or a file, and the concurrency
+   * @returns Per-row report; failed rows carry an error instead of a link
+   */
+  async function bulkCreate(request: BulkCreateRequest): Promise<BulkReport> {
+    try {
+      return await invoke<BulkReport>('bulk_create', { request })
+    } catch (error) {
+      console.error('Failed to create secrets in bulk:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Export a bulk creation report, as JSON for a `.json` path and CSV otherwise
+   * The file holds links and passphrases and is only readable by the user
+   */
+  async function exportBulkReport(report: BulkReport, path: string): Promise<void> {
+    try {
+      await invoke('export_bulk_report', { report, path })
+    } catch (error) {
+      console.error('Failed to export bulk report:', error)
+      throw error
+    }
+  }
+
   /**
    * Get secret metadata without burning it
    * @param metadataKey - The metadata key for the secret
@@ -85,9 +496,235 @@ export function useSecretRepository() {
     }
   }
 
+  /**
+   * Copy a secret link to the clipboard
+   * The clipboard is cleared after the timeout if it still holds the link
+   * @param link - The secret link
+   * @param clearAfterSecs - Seconds before clearing (0 disables clearing)
+   */
+  async function copyLink(link: string, clearAfterSecs?: number): Promise<void> {
+    try {
+      await invoke('copy_link', { link, clearAfterSecs })
+    } catch (error) {
+      console.error('Failed to copy link:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Copy a revealed secret to the clipboard
+   * The clipboard is cleared after the timeout if it still holds the secret
+   * @param secret - The revealed secret
+   * @param clearAfterSecs - Seconds before clearing (0 disables clearing)
+   */
+  async function copySecret(secret: string, clearAfterSecs?: number): Promise<void> {
+    try {
+      await invoke('copy_secret', { secret, clearAfterSecs })
+    } catch (error) {
+      console.error('Failed to copy secret:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Render a share link as a QR code
+   * Rendering happens locally, the link is never sent to a third party
+   * @param link - The secret link
+   * @param errorCorrection - QR error correction level
+   * @returns SVG and PNG data URIs
+   */
+  async function renderQr(
+    link: string,
+    errorCorrection: ErrorCorrection = 'medium'
+  ): Promise<QrImage> {
+    try {
+      const response = await invoke<QrImage>('render_qr', {
+        request: { link, error_correction: errorCorrection }
+      })
+      return response
+    } catch (error) {
+      console.error('Failed to render QR code:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Subscribe to onetimesecret:// links opened while the app runs
+   * Actions queued before subscribing (e.g. the link that launched the app)
+   * are delivered immediately. The handler should confirm with the user
+   * before retrieving, since retrieval burns the secret.
+   * @param handler - Called once per link action
+   * @returns Function that removes the subscription
+   */
+  async function onDeepLink(
+    handler: (action: DeepLinkAction) => void
+  ): Promise<UnlistenFn> {
+    const drain = async () => {
+      const actions = await invoke<DeepLinkAction[]>('take_deep_links')
+      actions.forEach(handler)
+    }
+
+    const unlisten = await listen('deep-link', () => {
+      drain().catch((error) => console.error('Failed to take deep links:', error))
+    })
+    await drain()
+    return unlisten
+  }
+
+  /**
+   * Subscribe to files passed on the command line or dropped on the
+   * window, including those a second launch forwards to this instance
+   * @param handler - Called with the file paths to share
+   * @returns Function that removes the subscription
+   */
+  async function onOpenFiles(handler: (paths: string[]) => void): Promise<UnlistenFn> {
+    const drain = async () => {
+      const paths = await invoke<string[]>('take_open_files')
+      if (paths.length > 0) {
+        handler(paths)
+      }
+    }
+
+    const unlisten = await listen('open-files', () => {
+      drain().catch((error) => console.error('Failed to take open files:', error))
+    })
+    await drain()
+    return unlisten
+  }
+
+  /**
+   * List receipts of secrets created from this app, newest first
+   * @param limit - Maximum number of entries
+   */
+  async function listHistory(limit?: number): Promise<HistoryEntry[]> {
+    try {
+      return await invoke<HistoryEntry[]>('list_history', { limit })
+    } catch (error) {
+      console.error('Failed to list history:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Ask someone to send a secret
+   * The link carries a public key made for this request; retrieving the
+   * answer decrypts it and closes the request
+   * @returns The history entry of the request link
+   */
+  async function createIncomingRequest(request: NewIncomingRequest): Promise<HistoryEntry> {
+    try {
+      return await invoke<HistoryEntry>('create_incoming_request', { request })
+    } catch (error) {
+      console.error('Failed to create request:', error)
+      throw error
+    }
+  }
+
+  /**
+   * List the user's requests for secrets, newest first
+   */
+  async function listIncomingRequests(): Promise<HistoryEntry[]> {
+    try {
+      return await invoke<HistoryEntry[]>('list_incoming_requests')
+    } catch (error) {
+      console.error('Failed to list requests:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Stop waiting for an answer and delete the request's key
+   */
+  async function cancelIncomingRequest(id: string): Promise<void> {
+    try {
+      await invoke('cancel_incoming_request', { id })
+    } catch (error) {
+      console.error('Failed to cancel request:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Answer someone's request with a secret encrypted to its key
+   * @returns Response with the link to send back
+   */
+  async function answerIncomingRequest(answer: RequestAnswer): Promise<CreateSecretResponse> {
+    try {
+      return await invoke<CreateSecretResponse>('answer_incoming_request', { answer })
+    } catch (error) {
+      console.error('Failed to answer request:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Burn or refresh many receipts at once
+   * Progress arrives through `onOperationProgress`; the batch can be stopped
+   * with `cancelOperation` using the same ID
+   * @param request - Action, selection and an ID for the batch
+   * @returns Outcome per receipt, in the order of the selection
+   */
+  async function runBatch(request: BatchRequest): Promise<BatchReport> {
+    try {
+      return await invoke<BatchReport>('run_batch', { request })
+    } catch (error) {
+      console.error('Failed to run batch:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Stop a running operation such as a batch, bulk creation or file upload
+   * Operations report what they finished; a split secret that was cut short
+   * fails with a cancellation error and its chunks are burned
+   * @returns Whether the operation was still running
+   */
+  async function cancelOperation(id: string): Promise<boolean> {
+    try {
+      return await invoke<boolean>('cancel_operation', { id })
+    } catch (error) {
+      console.error('Failed to cancel operation:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Subscribe to progress of running operations
+   * @param handler - Called after each step, match `id` to the request
+   * @returns Function that removes the subscription
+   */
+  async function onOperationProgress<T = unknown>(
+    handler: (progress: OperationProgress<T>) => void
+  ): Promise<UnlistenFn> {
+    return await listen<OperationProgress<T>>('operation-progress', (event) => handler(event.payload))
+  }
+
   return {
     createSecret,
+    createChunkedSecret,
     retrieveSecret,
-    getSecretMetadata
+    shareFile,
+    shareFiles,
+    previewFiles,
+    saveReceivedFile,
+    discardReceivedFile,
+    splitSecret,
+    combineShares,
+    bulkCreate,
+    exportBulkReport,
+    getSecretMetadata,
+    copyLink,
+    copySecret,
+    renderQr,
+    onDeepLink,
+    onOpenFiles,
+    listHistory,
+    createIncomingRequest,
+    listIncomingRequests,
+    cancelIncomingRequest,
+    answerIncomingRequest,
+    runBatch,
+    cancelOperation,
+    onOperationProgress
   }
 }
@@ -2,15 +2,65 @@ import { defineStore } from 'pinia'
 import { ref } from 'vue'
 import { invoke } from '@tauri-apps/api/core'
 
+export type ProxyMode = 'system' | 'direct' | 'manual'
+
+export interface ProxySettings {
+  mode: ProxyMode
+  url?: string
+  username?: string
+  password?: string
+  no_proxy?: string[]
+}
+
+/** Client certificate for mTLS gateways; passwords and PEM data are kept in the keychain */
+export type ClientCertificate =
+  | { source: 'pem'; certificate: string; key: string }
+  | { source: 'pkcs12'; path: string; password?: string }
+  | { source: 'keychain'; pem?: string }
+
+export interface TlsSettings {
+  /** Paths of PEM files with extra trusted CA certificates */
+  ca_certificates?: string[]
+  /** SPKI pins as `sha256/<base64>` */
+  spki_pins?: string[]
+  client_certificate?: ClientCertificate
+}
+
 export interface ApiConfig {
   baseUrl: string
   username: string
   apiKey?: string
+  proxy?: ProxySettings
+  tls?: TlsSettings
+  /** Developer option: plain HTTP to localhost and private addresses only */
+  allow_insecure_http?: boolean
 }
 
+export interface ProfileIndex {
+  active: string
+  names: string[]
+}
+
+export type ConfigSource = 'environment' | 'config_file' | 'keychain'
+
+export interface ConfigReport {
+  profile?: string
+  config_file?: string
+  fields: { name: string; value?: string; source?: ConfigSource }[]
+}
+
+export type ConnectionFailure =
+  | 'proxy'
+  | 'certificate'
+  | 'handshake'
+  | 'target'
+  | 'authentication'
+  | 'configuration'
+
 export interface TestConnectionResult {
   success: boolean
   error?: string
+  failure?: ConnectionFailure
 }
 
 export const useApiStore = defineStore('api', () => {
@@ -69,12 +119,72 @@ export const useApiStore = defineStore('api', () => {
     }
   }
 
+  /**
+   * Show the configuration in effect and where each value came from
+   * Environment variables and a config file override the keychain
+   */
+  async function showResolvedConfig(): Promise<ConfigReport> {
+    try {
+      return await invoke<ConfigReport>('show_resolved_config')
+    } catch (error) {
+      console.error('Failed to resolve config:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Move a corrupted stored configuration aside so it can be entered again
+   * Returns false when the stored configuration was readable
+   */
+  async function recoverConfig(profile?: string): Promise<boolean> {
+    try {
+      const recovered = await invoke<boolean>('recover_api_config', { profile })
+      if (recovered) {
+        isConfigured.value = false
+      }
+      return recovered
+    } catch (error) {
+      console.error('Failed to recover config:', error)
+      throw error
+    }
+  }
+
+  /**
+   * List profiles and the active one
+   */
+  async function listProfiles(): Promise<ProfileIndex> {
+    try {
+      return await invoke<ProfileIndex>('list_profiles')
+    } catch (error) {
+      console.error('Failed to list profiles:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Switch the profile used by all API commands and reload its config
+   */
+  async function setActiveProfile(profile: string): Promise<void> {
+    try {
+      await invoke('set_active_profile', { profile })
+      isConfigured.value = false
+      await loadConfig()
+    } catch (error) {
+      console.error('Failed to switch profile:', error)
+      throw error
+    }
+  }
+
   return {
     isConfigured,
     baseUrl,
     username,
     loadConfig,
     saveConfig,
-    testConnection
+    testConnection,
+    showResolvedConfig,
+    recoverConfig,
+    listProfiles,
+    setActiveProfile
   }
 })
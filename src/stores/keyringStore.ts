@@ -0,0 +1,122 @@
+import { defineStore } from 'pinia'
+import { ref } from 'vue'
+import { invoke } from '@tauri-apps/api/core'
+
+export type KeyKind = 'age' | 'openpgp'
+
+export interface RecipientKey {
+  name: string
+  kind: KeyKind
+  /** `age1…` recipient or an armored OpenPGP public key */
+  public_key: string
+  added_at: number
+}
+
+export interface OwnKeys {
+  /** The `age1…` recipient others encrypt to, if an age key is set up */
+  age?: string
+  /** Whether an OpenPGP private key was imported */
+  openpgp: boolean
+}
+
+export const useKeyringStore = defineStore('keyring', () => {
+  const recipients = ref<RecipientKey[]>([])
+  const ownKeys = ref<OwnKeys>({ openpgp: false })
+
+  /**
+   * Load known recipients and the state of the user's own keys
+   */
+  async function load(): Promise<void> {
+    try {
+      recipients.value = await invoke<RecipientKey[]>('list_recipient_keys')
+      ownKeys.value = await invoke<OwnKeys>('get_own_keys')
+    } catch (error) {
+      console.error('Failed to load keyring:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Add or replace a recipient's public key
+   * @param name - Name to pick the recipient by
+   * @param publicKey - `age1…` recipient or armored OpenPGP public key
+   */
+  async function addRecipient(name: string, publicKey: string): Promise<RecipientKey> {
+    try {
+      const key = await invoke<RecipientKey>('add_recipient_key', { name, publicKey })
+      recipients.value = await invoke<RecipientKey[]>('list_recipient_keys')
+      return key
+    } catch (error) {
+      console.error('Failed to add recipient key:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Forget a recipient's public key
+   */
+  async function removeRecipient(name: string): Promise<void> {
+    try {
+      await invoke('remove_recipient_key', { name })
+      recipients.value = recipients.value.filter((key) => key.name !== name)
+    } catch (error) {
+      console.error('Failed to remove recipient key:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Create the user's age key in the OS keychain
+   * @returns The `age1…` recipient to give to senders
+   */
+  async function generateAgeKey(): Promise<string> {
+    try {
+      const recipient = await invoke<string>('generate_age_key')
+      ownKeys.value = { ...ownKeys.value, age: recipient }
+      return recipient
+    } catch (error) {
+      console.error('Failed to generate age key:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Import the user's OpenPGP secret key into the OS keychain
+   * @param secretKey - Armored secret key
+   * @param passphrase - Passphrase of the key, if it has one
+   */
+  async function importOpenpgpKey(secretKey: string, passphrase?: string): Promise<void> {
+    try {
+      await invoke('import_openpgp_key', { secretKey, passphrase })
+      ownKeys.value = { ...ownKeys.value, openpgp: true }
+    } catch (error) {
+      console.error('Failed to import OpenPGP key:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Delete one of the user's private keys
+   * Secrets already encrypted to it can no longer be read
+   */
+  async function deleteOwnKey(kind: KeyKind): Promise<void> {
+    try {
+      await invoke('delete_own_key', { kind })
+      ownKeys.value = await invoke<OwnKeys>('get_own_keys')
+    } catch (error) {
+      console.error('Failed to delete key:', error)
+      throw error
+    }
+  }
+
+  return {
+    recipients,
+    ownKeys,
+    load,
+    addRecipient,
+    removeRecipient,
+    generateAgeKey,
+    importOpenpgpKey,
+    deleteOwnKey
+  }
+})
@@ -0,0 +1,119 @@
+import { defineStore } from 'pinia'
+import { ref } from 'vue'
+import { invoke } from '@tauri-apps/api/core'
+
+export interface HotkeySettings {
+  quick_share?: string
+  show_window?: string
+}
+
+export interface AppSettings {
+  default_ttl: number
+  require_passphrase: boolean
+  default_recipient?: string
+  clipboard_clear_secs: number
+  history_retention_days: number
+  close_to_tray: boolean
+  hotkeys: HotkeySettings
+  /** Largest secret the app creates, in bytes; a local cap, not the server's limit */
+  local_max_secret_size: number
+}
+
+export type ConflictStrategy = 'skip' | 'overwrite' | 'rename'
+
+export interface ExportReport {
+  path: string
+  profiles: string[]
+  history_entries: number
+  includes_api_keys: boolean
+}
+
+export interface ImportReport {
+  imported: string[]
+  overwritten: string[]
+  renamed: { from: string; to: string }[]
+  skipped: string[]
+  history_entries: number
+  settings_imported: boolean
+}
+
+export const useSettingsStore = defineStore('settings', () => {
+  const settings = ref<AppSettings | null>(null)
+
+  /**
+   * Load user preferences
+   */
+  async function loadSettings(): Promise<AppSettings> {
+    try {
+      settings.value = await invoke<AppSettings>('get_settings')
+      return settings.value
+    } catch (error) {
+      console.error('Failed to load settings:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Save user preferences
+   * Invalid values and conflicting hotkeys are rejected and nothing is saved
+   */
+  async function updateSettings(updated: AppSettings): Promise<AppSettings> {
+    try {
+      settings.value = await invoke<AppSettings>('update_settings', { settings: updated })
+      return settings.value
+    } catch (error) {
+      console.error('Failed to save settings:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Export profiles, settings and history to a passphrase-encrypted file
+   * API keys are only included when explicitly requested
+   */
+  async function exportBundle(
+    path: string,
+    passphrase: string,
+    includeApiKeys = false
+  ): Promise<ExportReport> {
+    try {
+      return await invoke<ExportReport>('export_bundle', {
+        request: { path, passphrase, include_api_keys: includeApiKeys }
+      })
+    } catch (error) {
+      console.error('Failed to export bundle:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Import a bundle, resolving existing profile names with `onConflict`
+   */
+  async function importBundle(
+    path: string,
+    passphrase: string,
+    onConflict: ConflictStrategy = 'skip',
+    importSettings = false
+  ): Promise<ImportReport> {
+    try {
+      const report = await invoke<ImportReport>('import_bundle', {
+        request: { path, passphrase, on_conflict: onConflict, import_settings: importSettings }
+      })
+      if (report.settings_imported) {
+        await loadSettings()
+      }
+      return report
+    } catch (error) {
+      console.error('Failed to import bundle:', error)
+      throw error
+    }
+  }
+
+  return {
+    settings,
+    loadSettings,
+    updateSettings,
+    exportBundle,
+    importBundle
+  }
+})
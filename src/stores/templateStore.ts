@@ -0,0 +1,80 @@
+import { defineStore } from 'pinia'
+import { ref } from 'vue'
+import { invoke } from '@tauri-apps/api/core'
+
+/** Whether secrets from a template need a passphrase */
+export type PassphraseRule = 'optional' | 'required' | 'generate'
+
+export interface TemplateField {
+  /** Shown in the form and in the secret, also the key of the value */
+  label: string
+  /** Allow several lines, e.g. for a VPN config */
+  multiline?: boolean
+  /** Allow the field to be left empty */
+  optional?: boolean
+}
+
+export interface SecretTemplate {
+  /** Lowercase letters, digits and dashes */
+  id: string
+  name: string
+  fields: TemplateField[]
+  /** TTL the form starts with, in seconds */
+  default_ttl?: number
+  passphrase: PassphraseRule
+}
+
+/** Template to render a secret from, with values keyed by field label */
+export interface TemplateInput {
+  id: string
+  fields: Record<string, string>
+}
+
+export const useTemplateStore = defineStore('templates', () => {
+  const templates = ref<SecretTemplate[]>([])
+
+  /**
+   * Load the templates stored on this device
+   */
+  async function load(): Promise<void> {
+    try {
+      templates.value = await invoke<SecretTemplate[]>('list_templates')
+    } catch (error) {
+      console.error('Failed to load templates:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Add a template or replace the one with the same ID
+   */
+  async function saveTemplate(template: SecretTemplate): Promise<void> {
+    try {
+      await invoke('save_template', { template })
+      templates.value = await invoke<SecretTemplate[]>('list_templates')
+    } catch (error) {
+      console.error('Failed to save template:', error)
+      throw error
+    }
+  }
+
+  /**
+   * Delete a template; secrets already created from it still parse
+   */
+  async function deleteTemplate(id: string): Promise<void> {
+    try {
+      await invoke('delete_template', { id })
+      templates.value = templates.value.filter((template) => template.id !== id)
+    } catch (error) {
+      console.error('Failed to delete template:', error)
+      throw error
+    }
+  }
+
+  return {
+    templates,
+    load,
+    saveTemplate,
+    deleteTemplate
+  }
+})